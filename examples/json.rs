@@ -5,32 +5,10 @@ use std::{
 
 use parlib::{
     inputs::Input,
-    parsers::{
-        and_p::KeepFirstOutputOnly, string_p::string_parser, ParseMatch, ParseWhile,
-        ParseWhileOrNothing,
-    },
+    parsers::{number_p::float, string_p::string_parser, ParseMatch, ParseWhileOrNothing},
     traits::Parser,
 };
 
-fn parse_integer() -> impl Parser<Output = String> {
-    ParseWhile(|c| c.is_numeric())
-}
-
-fn parse_float() -> impl Parser<Output = String> {
-    let p_whole = ParseWhile(|c| c.is_numeric());
-    let p_decimal = ParseWhileOrNothing(|c| c.is_numeric());
-
-    // Parse the whole part
-    p_whole
-        .and_then(ParseMatch('.'))
-        // Delete the separator (we dont need it, we just need to know it occurred)
-        .combine(KeepFirstOutputOnly)
-        // Parse the decimal part
-        .and_then(p_decimal)
-        // Now that we know the whole and the decimal part, we can join them
-        .with_mapping(&|(whole, decimal)| format!("{whole}.{decimal}"))
-}
-
 #[derive(Debug, PartialEq)]
 pub enum Primitives {
     True,
@@ -42,10 +20,7 @@ pub enum Primitives {
 }
 
 fn parse_number() -> impl Parser<Output = Primitives> {
-    parse_float().otherwise(parse_integer()).with_mapping(&|s| {
-        let numb = s.parse::<f64>().unwrap();
-        Primitives::Number(numb)
-    })
+    float().with_mapping(&Primitives::Number)
 }
 
 fn parse_string() -> impl Parser<Output = Primitives> {
@@ -6,9 +6,8 @@ use std::{
 use parlib::{
     inputs::Input,
     parsers::{
-        and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly},
-        string_p::string_parser,
-        ParseMatch, ParseWhile, ParseWhileOrNothing,
+        and_p::KeepSecondOutputOnly, number_p::float, string_p::string_parser, ParseMatch,
+        ParseWhile, ParseWhileOrNothing,
     },
     traits::Parser,
 };
@@ -33,26 +32,8 @@ fn parse_string() -> impl Parser<Output = Primitives> {
     string_parser().with_mapping(&|s| Primitives::String(s))
 }
 
-fn parse_integer() -> impl Parser<Output = String> {
-    ParseWhile(|c| c.is_numeric())
-}
-
-fn parse_float() -> impl Parser<Output = String> {
-    let p_whole = ParseWhile(|c| c.is_numeric());
-    let p_decimal = ParseWhileOrNothing(|c| c.is_numeric());
-
-    p_whole
-        .and_then(ParseMatch('.'))
-        .combine(KeepFirstOutputOnly)
-        .and_then(p_decimal)
-        .with_mapping(&|(whole, decimal)| format!("{whole}.{decimal}"))
-}
-
 fn parse_number() -> impl Parser<Output = Primitives> {
-    parse_float().otherwise(parse_integer()).with_mapping(&|s| {
-        let numb = s.parse::<f64>().unwrap();
-        Primitives::Number(numb)
-    })
+    float().with_mapping(&Primitives::Number)
 }
 
 pub fn parse_prim() -> impl Parser<Output = Primitives> {
@@ -100,13 +81,9 @@ impl Parser for CompoundParser {
             .parse(&rest)?;
 
         let mut v: Vec<Expression> = vec![];
-        loop {
-            if let Ok((exp, r)) = expression_parse().parse(&rest) {
-                v.push(exp);
-                rest = r;
-            } else {
-                break;
-            }
+        while let Ok((exp, r)) = expression_parse().parse(&rest) {
+            v.push(exp);
+            rest = r;
         }
         // find the closing bracket
         let (_, f_rest) = ws.and_then(cb).parse(&rest)?;
@@ -130,7 +107,7 @@ fn main() {
             .expect("Error reading user input");
         let par = expression_parse();
 
-        if buffer == String::from("exit\n") {
+        if buffer == "exit\n" {
             break;
         }
 
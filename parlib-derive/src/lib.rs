@@ -0,0 +1,305 @@
+//! `#[derive(Parse)]`, which generates `parlib::derive::Parse` implementations from type
+//! definitions. Use it through the `derive` feature of `parlib`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parenthesized, parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Expr, Field,
+    Fields, GenericArgument, LitStr, PathArguments, Token, Type,
+};
+
+/// Generate a `parlib::derive::Parse` implementation for a struct or an enum.
+///
+/// The fields of a struct are parsed one after another, each with its own `Parse`
+/// implementation. The variants of an enum are tried in order, and the first one that
+/// parses is used. Whitespace is skipped before every literal and every field, so types
+/// can refer to each other, and to themselves, freely (though not left recursively).
+///
+/// These attributes can be used on structs and variants, `#[parse(...)]`:
+///
+/// - `literal = "..."`: a literal that comes before the fields. A unit variant without
+///   one is parsed from its name.
+/// - `delimited("open", "close")`: literals around the fields.
+/// - `sep_by` and `with` (see below), for structs and variants with a single field.
+///
+/// And on fields:
+///
+/// - `literal = "..."`: a literal that comes before the field, such as `=` in `x = 1`.
+/// - `delimited("open", "close")`: literals around the field.
+/// - `sep_by = "..."`: for `Vec` fields, the values are separated by this literal,
+///   rather than just by whitespace.
+/// - `with = "..."`: parse the field with this parser expression instead.
+///
+/// Literals that end like a word, such as `true`, are parsed as keywords. If none of the
+/// variants of an enum make any progress, the error is `Expected(<name of the enum>)`.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::derive::{value, Parse};
+/// use parlib::traits::Parser;
+///
+/// #[derive(Debug, PartialEq, Parse)]
+/// enum Primitives {
+///     #[parse(literal = "true")]
+///     True,
+///     #[parse(literal = "false")]
+///     False,
+///     String(String),
+///     Number(f64),
+///     #[parse(sep_by = ",", delimited("[", "]"))]
+///     Array(Vec<Primitives>),
+/// }
+///
+/// let (parsed, _) = value::<Primitives>().parse(&r#"[true, "a", [1.5]]"#.into()).unwrap();
+/// assert_eq!(
+///     parsed,
+///     Primitives::Array(vec![
+///         Primitives::True,
+///         Primitives::String("a".to_string()),
+///         Primitives::Array(vec![Primitives::Number(1.5)]),
+///     ])
+/// );
+/// ```
+#[proc_macro_derive(Parse, attributes(parse))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// The options of a `#[parse(...)]` attribute
+#[derive(Default)]
+struct Options {
+    literal: Option<LitStr>,
+    delimited: Option<(LitStr, LitStr)>,
+    sep_by: Option<LitStr>,
+    with: Option<Expr>,
+}
+
+impl Options {
+    fn from_attributes(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Options::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("parse")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("literal") {
+                    options.literal = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("sep_by") {
+                    options.sep_by = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("with") {
+                    let expr: LitStr = meta.value()?.parse()?;
+                    options.with = Some(expr.parse()?);
+                } else if meta.path.is_ident("delimited") {
+                    let content;
+                    parenthesized!(content in meta.input);
+                    let open = content.parse()?;
+                    content.parse::<Token![,]>()?;
+                    let close = content.parse()?;
+                    options.delimited = Some((open, close));
+                } else {
+                    return Err(meta.error("unknown parse option"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+
+    /// Field options on a struct or a variant apply to its only field
+    fn field_options(&mut self, fields: &Fields) -> syn::Result<Option<Options>> {
+        if self.sep_by.is_none() && self.with.is_none() {
+            return Ok(None);
+        }
+        if fields.len() != 1 {
+            return Err(syn::Error::new_spanned(
+                fields,
+                "`sep_by` and `with` can only be used here if there is a single field",
+            ));
+        }
+        Ok(Some(Options {
+            sep_by: self.sep_by.take(),
+            with: self.with.take(),
+            ..Options::default()
+        }))
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::parlib::derive::Parse));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let options = Options::from_attributes(&input.attrs)?;
+            sequence(quote!(#name), options, &data.fields, None)?
+        }
+        Data::Enum(data) => {
+            let mut variants = vec![];
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let options = Options::from_attributes(&variant.attrs)?;
+                let default = ident.to_string();
+                let sequence = sequence(
+                    quote!(#name::#ident),
+                    options,
+                    &variant.fields,
+                    Some(&default),
+                )?;
+                variants.push(quote! {
+                    match (|| -> ::parlib::type_alias::ParserRes<Self> { #sequence })() {
+                        Ok(parsed) => return Ok(parsed),
+                        Err(e) if e.is_incomplete() => return Err(e),
+                        Err(e) => ::parlib::grammar::keep_furthest(&mut best, e),
+                    }
+                });
+            }
+            let label = name.to_string();
+            quote! {
+                let ((), input) = ::parlib::derive::whitespace(input)?;
+                let input = &input;
+                let mut best = None;
+                #(#variants)*
+                Err(::parlib::grammar::rule_error(#label, input, best))
+            }
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Parse` can not be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::parlib::derive::Parse for #name #ty_generics #where_clause {
+            fn parse_value(
+                input: &::parlib::inputs::Input,
+            ) -> ::parlib::type_alias::ParserRes<Self> {
+                #body
+            }
+        }
+    })
+}
+
+/// Parse a literal from `rest`
+fn literal(literal: &str) -> TokenStream2 {
+    quote! {
+        let (_, rest) = ::parlib::derive::literal(&rest, #literal)?;
+    }
+}
+
+/// Parse the fields of a struct or a variant from `input`, and build it with `constructor`.
+/// Unit variants are parsed from `default` if they have no literal.
+fn sequence(
+    constructor: TokenStream2,
+    mut options: Options,
+    fields: &Fields,
+    default: Option<&str>,
+) -> syn::Result<TokenStream2> {
+    let mut only_field = options.field_options(fields)?;
+    let mut steps = vec![];
+    match (&options.literal, fields, default) {
+        (Some(lit), _, _) => steps.push(literal(&lit.value())),
+        (None, Fields::Unit, Some(default)) => steps.push(literal(default)),
+        _ => {}
+    }
+    if let Some((open, _)) = &options.delimited {
+        steps.push(literal(&open.value()));
+    }
+
+    let mut names = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let name = format_ident!("field_{}", i);
+        let mut field_options = Options::from_attributes(&field.attrs)?;
+        if let Some(only) = only_field.take() {
+            field_options.sep_by = only.sep_by.or(field_options.sep_by);
+            field_options.with = only.with.or(field_options.with);
+        }
+        steps.push(field_step(&name, field, field_options)?);
+        names.push(name);
+    }
+
+    if let Some((_, close)) = &options.delimited {
+        steps.push(literal(&close.value()));
+    }
+
+    let build = match fields {
+        Fields::Named(named) => {
+            let idents = named.named.iter().map(|field| &field.ident);
+            quote!(#constructor { #(#idents: #names),* })
+        }
+        Fields::Unnamed(_) => quote!(#constructor(#(#names),*)),
+        Fields::Unit => quote!(#constructor),
+    };
+    Ok(quote! {
+        let rest = input.clone();
+        #(#steps)*
+        Ok((#build, rest))
+    })
+}
+
+/// Parse one field from `rest` into `name`
+fn field_step(name: &syn::Ident, field: &Field, options: Options) -> syn::Result<TokenStream2> {
+    if options.sep_by.is_some() && options.with.is_some() {
+        return Err(syn::Error::new_spanned(
+            field,
+            "`sep_by` and `with` can not be used together",
+        ));
+    }
+
+    let mut steps = vec![];
+    if let Some(lit) = &options.literal {
+        steps.push(literal(&lit.value()));
+    }
+    if let Some((open, _)) = &options.delimited {
+        steps.push(literal(&open.value()));
+    }
+
+    let ty = &field.ty;
+    steps.push(match (&options.sep_by, &options.with) {
+        (Some(separator), _) => {
+            let item = vec_item(ty).ok_or_else(|| {
+                syn::Error::new_spanned(ty, "`sep_by` can only be used on `Vec` fields")
+            })?;
+            quote! {
+                let (#name, rest) = ::parlib::derive::separated::<#item>(&rest, #separator)?;
+            }
+        }
+        (None, Some(with)) => quote! {
+            let ((), rest) = ::parlib::derive::whitespace(&rest)?;
+            let (#name, rest): (#ty, _) = ::parlib::traits::Parser::parse(&(#with), &rest)?;
+        },
+        (None, None) => quote! {
+            let (#name, rest) = <#ty as ::parlib::derive::Parse>::parse_value(&rest)?;
+        },
+    });
+
+    if let Some((_, close)) = &options.delimited {
+        steps.push(literal(&close.value()));
+    }
+    Ok(quote!(#(#steps)*))
+}
+
+/// The `T` of a `Vec<T>`
+fn vec_item(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let last = path.path.segments.last()?;
+    if last.ident != "Vec" {
+        return None;
+    }
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(item) => Some(item),
+            _ => None,
+        },
+        _ => None,
+    }
+}
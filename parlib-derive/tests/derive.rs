@@ -0,0 +1,141 @@
+use parlib::{
+    derive::{value, Parse},
+    errors::ParsingErrorKind,
+    parsers::lexeme_p::identifier,
+    traits::Parser,
+};
+
+#[derive(Debug, PartialEq, Parse)]
+enum Primitives {
+    #[parse(literal = "true")]
+    True,
+    #[parse(literal = "false")]
+    False,
+    String(String),
+    Number(f64),
+    #[parse(sep_by = ",", delimited("[", "]"))]
+    Array(Vec<Primitives>),
+    #[parse(sep_by = ",", delimited("{", "}"))]
+    Object(Vec<Member>),
+}
+
+#[derive(Debug, PartialEq, Parse)]
+struct Member {
+    key: String,
+    #[parse(literal = ":")]
+    value: Primitives,
+}
+
+// (add 2 (neg 1))
+#[derive(Debug, PartialEq, Parse)]
+enum Expression {
+    Prim(Primitives),
+    #[parse(delimited("(", ")"))]
+    Compound {
+        #[parse(with = "identifier()")]
+        ident: String,
+        params: Vec<Expression>,
+    },
+}
+
+#[test]
+fn json() {
+    let source = r#" { "a" : [1, true], "b": {}, "c": "x" } "#;
+    let (parsed, rest) = value::<Primitives>().parse(&source.into()).unwrap();
+    assert_eq!(
+        parsed,
+        Primitives::Object(vec![
+            Member {
+                key: "a".to_string(),
+                value: Primitives::Array(vec![Primitives::Number(1.0), Primitives::True]),
+            },
+            Member {
+                key: "b".to_string(),
+                value: Primitives::Object(vec![]),
+            },
+            Member {
+                key: "c".to_string(),
+                value: Primitives::String("x".to_string()),
+            },
+        ])
+    );
+    assert_eq!(rest.source, " ".to_string());
+
+    // Literals that end like a word are keywords
+    assert!(value::<Primitives>().parse(&"truex".into()).is_err());
+}
+
+#[test]
+fn recursion() {
+    let (parsed, _) = value::<Expression>()
+        .parse(&"(add 2\n  (neg false))".into())
+        .unwrap();
+    assert_eq!(
+        parsed,
+        Expression::Compound {
+            ident: "add".to_string(),
+            params: vec![
+                Expression::Prim(Primitives::Number(2.0)),
+                Expression::Compound {
+                    ident: "neg".to_string(),
+                    params: vec![Expression::Prim(Primitives::False)],
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn errors() {
+    let err = value::<Primitives>().parse(&"  nil".into()).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ParsingErrorKind::Expected("Primitives".to_string())
+    );
+    assert_eq!(err.col(), 2);
+
+    // Once a variant has made progress, the error that got the furthest is kept
+    let err = value::<Primitives>().parse(&"[1, 2,]".into()).unwrap_err();
+    assert_eq!(err.col(), 6);
+    let err = value::<Expression>().parse(&"(add 1".into()).unwrap_err();
+    assert_eq!(err.col(), 6);
+}
+
+#[derive(Debug, PartialEq, Parse)]
+enum Unit {
+    Metres,
+    #[parse(literal = "ft")]
+    Feet,
+}
+
+#[derive(Debug, PartialEq, Parse)]
+#[parse(literal = "let")]
+struct Binding<T> {
+    #[parse(with = "identifier()")]
+    name: String,
+    #[parse(literal = "=")]
+    value: T,
+    unit: Option<Unit>,
+}
+
+#[test]
+fn generics_and_options() {
+    let (parsed, _) = value::<Binding<u32>>()
+        .parse(&"let x = 3 Metres".into())
+        .unwrap();
+    assert_eq!(
+        parsed,
+        Binding {
+            name: "x".to_string(),
+            value: 3,
+            unit: Some(Unit::Metres),
+        }
+    );
+
+    let (parsed, _) = value::<Binding<bool>>()
+        .parse(&"let y=true".into())
+        .unwrap();
+    assert_eq!(parsed.unit, None);
+
+    assert!(value::<Binding<u32>>().parse(&"lety = 1".into()).is_err());
+}
@@ -0,0 +1,188 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{AsyncRead, Stream};
+
+use crate::{
+    stream::{StreamError, StreamParser},
+    traits::Parser,
+};
+
+/// Parse a stream of values, one after another, from anything that implements
+/// `futures::AsyncRead`, such as a socket. This does not depend on any async runtime.
+///
+/// This is the async version of `stream::ReadParser`: more source is read whenever a
+/// value is cut off at the end of what has been read so far, and errors are reported
+/// with their offset in the stream. It is a `Stream` of the parsed values, which ends
+/// with the source, or after the first error.
+///
+/// # Example
+///
+/// ```rust
+/// use futures::{executor::block_on, StreamExt};
+/// use parlib::async_stream::AsyncReadParser;
+/// use parlib::parsers::{number_p::integer, ParseMatch};
+/// use parlib::traits::Parser;
+///
+/// let source = futures::io::Cursor::new("1\n22\n333\n");
+/// let line = integer::<u32>().and_then(ParseMatch("\n"));
+/// let numbers = AsyncReadParser::new(source, line)
+///     .chunk_size(2)
+///     .map(|parsed| parsed.unwrap().0)
+///     .collect::<Vec<_>>();
+/// assert_eq!(block_on(numbers), vec![1, 22, 333]);
+/// ```
+#[derive(Debug)]
+pub struct AsyncReadParser<R, P> {
+    reader: R,
+    stream: StreamParser<P>,
+    chunk: Vec<u8>,
+}
+
+impl<R, P> AsyncReadParser<R, P>
+where
+    R: AsyncRead + Unpin,
+    P: Parser + Unpin,
+{
+    pub fn new(reader: R, parser: P) -> Self {
+        AsyncReadParser {
+            reader,
+            stream: StreamParser::new(parser),
+            chunk: vec![0; 8 * 1024],
+        }
+    }
+
+    /// How many bytes to read at a time, 8 KiB by default
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk = vec![0; size.max(1)];
+        self
+    }
+
+    /// The parser over the source that has been read so far
+    pub fn stream(&self) -> &StreamParser<P> {
+        &self.stream
+    }
+}
+
+impl<R, P> Stream for AsyncReadParser<R, P>
+where
+    R: AsyncRead + Unpin,
+    P: Parser + Unpin,
+{
+    type Item = Result<P::Output, StreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.stream.next_output() {
+                Ok(Some(parsed)) => return Poll::Ready(Some(Ok(parsed))),
+                Err(e) => return Poll::Ready(Some(Err(e))),
+                Ok(None) if this.stream.is_stopped() => return Poll::Ready(None),
+                Ok(None) => {}
+            }
+
+            match Pin::new(&mut this.reader).poll_read(cx, &mut this.chunk) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => this.stream.finish(),
+                Poll::Ready(Ok(read)) => {
+                    if let Err(e) = this.stream.feed(&this.chunk[..read]) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(this.stream.read_failed(e)))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_async_stream {
+    use std::io;
+
+    use futures::{
+        channel::mpsc, executor::block_on, AsyncRead, FutureExt, StreamExt, TryStreamExt,
+    };
+
+    use super::AsyncReadParser;
+    use crate::{
+        parsers::{and_p::KeepFirstOutputOnly, lexeme_p::keyword, number_p::integer, ParseMatch},
+        stream::StreamError,
+        traits::Parser,
+    };
+
+    type Sender = mpsc::UnboundedSender<io::Result<Vec<u8>>>;
+
+    /// An in memory pipe, where the reader waits for whatever is sent to the sender
+    fn pipe() -> (Sender, impl AsyncRead + Unpin) {
+        let (sender, receiver) = mpsc::unbounded();
+        (sender, receiver.into_async_read())
+    }
+
+    fn send(sender: &Sender, frame: &str) {
+        sender
+            .unbounded_send(Ok(frame.as_bytes().to_vec()))
+            .unwrap();
+    }
+
+    // A command of the form `PING <n>\r\n`
+    fn command() -> impl Parser<Output = u32> + Unpin {
+        integer::<u32>()
+            .preceed(keyword("PING").and_then(ParseMatch(" ")))
+            .and_then(ParseMatch("\r\n"))
+            .combine(KeepFirstOutputOnly)
+    }
+
+    #[test]
+    fn partial_frames() {
+        let (sender, reader) = pipe();
+        let mut commands = AsyncReadParser::new(reader, command());
+
+        send(&sender, "PING 1\r\nPI");
+        assert_eq!(commands.next().now_or_never().unwrap().unwrap().unwrap(), 1);
+
+        // The second command has only been partly sent, so the stream waits for the rest
+        assert!(commands.next().now_or_never().is_none());
+        send(&sender, "NG 2");
+        assert!(commands.next().now_or_never().is_none());
+        send(&sender, "3\r\nPING 4\r\n");
+        assert_eq!(
+            commands.next().now_or_never().unwrap().unwrap().unwrap(),
+            23
+        );
+        assert_eq!(commands.next().now_or_never().unwrap().unwrap().unwrap(), 4);
+
+        drop(sender);
+        assert!(commands.next().now_or_never().unwrap().is_none());
+    }
+
+    #[test]
+    fn errors_at_stream_offsets() {
+        let (sender, reader) = pipe();
+        for chunk in ["PING 1\r\n", "PING 2\r", "\nPONG 3\r\n"] {
+            send(&sender, chunk);
+        }
+        drop(sender);
+
+        let results = block_on(AsyncReadParser::new(reader, command()).collect::<Vec<_>>());
+        assert_eq!(results.len(), 3);
+        match &results[2] {
+            Err(StreamError::Parsing { error, offset }) => {
+                assert_eq!((error.line(), error.col()), (2, 0));
+                assert_eq!(*offset, 16);
+            }
+            other => panic!("expected a parsing error, found {other:?}"),
+        }
+
+        let (sender, reader) = pipe();
+        send(&sender, "PING 1\r\n");
+        sender
+            .unbounded_send(Err(io::Error::other("connection reset")))
+            .unwrap();
+        let results = block_on(AsyncReadParser::new(reader, command()).try_collect::<Vec<_>>());
+        assert!(matches!(results, Err(StreamError::Io(_))));
+    }
+}
@@ -0,0 +1,280 @@
+//! Parsing values straight into their types, see `Parse`
+
+use std::{fmt::Debug, marker::PhantomData};
+
+use crate::{
+    describe::Description,
+    inputs::Input,
+    parsers::{
+        char_p::is_ident_continue,
+        lexeme_p::{keyword, TokenParser},
+        number_p::{integer, FloatParser},
+        repeat_p::{optional, RepeatParser},
+        string_p::StringParser,
+        ParseMatch,
+    },
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Generate a `Parse` implementation for a struct or an enum, see the `parlib-derive` crate
+#[cfg(feature = "derive")]
+pub use parlib_derive::Parse;
+
+/// A type that knows how to parse itself. Any whitespace before the value is skipped, but
+/// whitespace after it is left in the rest of the input.
+///
+/// This is implemented for the numbers, `bool` (`true` or `false`), `String` (a double
+/// quoted string, see `StringParser`), and for `Box`, `Option` and `Vec` of anything that
+/// implements it. With the `derive` feature, it can be derived for structs and enums
+/// with `#[derive(Parse)]`.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::derive::{value, Parse};
+/// use parlib::inputs::Input;
+/// use parlib::traits::Parser;
+/// use parlib::type_alias::ParserRes;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Point(i32, i32);
+///
+/// impl Parse for Point {
+///     fn parse_value(input: &Input) -> ParserRes<Self> {
+///         let (x, rest) = i32::parse_value(input)?;
+///         let (y, rest) = i32::parse_value(&rest)?;
+///         Ok((Point(x, y), rest))
+///     }
+/// }
+///
+/// let (parsed, _) = value::<Vec<Point>>().parse(&"1 2  3 4".into()).unwrap();
+/// assert_eq!(parsed, vec![Point(1, 2), Point(3, 4)]);
+/// ```
+pub trait Parse: Sized + Debug {
+    fn parse_value(input: &Input) -> ParserRes<Self>;
+}
+
+/// The parser of a type that implements `Parse`, see `value`
+pub struct ValueParser<T> {
+    output: PhantomData<fn() -> T>,
+}
+
+impl<T> Debug for ValueParser<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ValueParser<{}>", std::any::type_name::<T>())
+    }
+}
+
+impl<T> Clone for ValueParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ValueParser<T> {}
+
+impl<T> Parser for ValueParser<T>
+where
+    T: Parse,
+{
+    type Output = T;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        T::parse_value(input)
+    }
+
+    fn describe(&self) -> Description {
+        Description::opaque::<T>()
+    }
+}
+
+/// Parse a value of some type that implements `Parse`
+pub fn value<T>() -> ValueParser<T>
+where
+    T: Parse,
+{
+    ValueParser {
+        output: PhantomData,
+    }
+}
+
+/// Skip any whitespace, see `Parse`
+#[doc(hidden)]
+pub fn whitespace(input: &Input) -> ParserRes<()> {
+    TokenParser::new().whitespace().parse(input)
+}
+
+/// Skip any whitespace, and then parse a literal. Literals that end like a word are
+/// parsed as keywords, so that `true` does not match the start of `trueish`.
+#[doc(hidden)]
+pub fn literal(input: &Input, literal: &str) -> ParserRes<String> {
+    let ((), rest) = whitespace(input)?;
+    if literal.ends_with(is_ident_continue) {
+        keyword(literal).parse(&rest)
+    } else {
+        ParseMatch(literal).parse(&rest)
+    }
+}
+
+/// Parse any number of values separated by a literal, with no separator after the last
+/// one. A first value that fails after making some progress is an error, rather than an
+/// empty list.
+#[doc(hidden)]
+pub fn separated<T>(input: &Input, separator: &str) -> ParserRes<Vec<T>>
+where
+    T: Parse,
+{
+    let ((), start) = whitespace(input)?;
+    let (first, mut rest) = match T::parse_value(&start) {
+        Ok(parsed) => parsed,
+        Err(e) if e.is_incomplete() || (e.line(), e.col()) != (start.line, start.col) => {
+            return Err(e)
+        }
+        Err(_) => return Ok((vec![], input.clone())),
+    };
+
+    let mut values = vec![first];
+    loop {
+        // Another separator may still follow in the next part of a partial input
+        let after = match literal(&rest, separator) {
+            Ok((_, after)) => after,
+            Err(e) if e.is_incomplete() => return Err(e),
+            Err(_) => return Ok((values, rest)),
+        };
+        let (parsed, after) = T::parse_value(&after)?;
+        values.push(parsed);
+        rest = after;
+    }
+}
+
+fn skip_whitespace<P>(input: &Input, parser: P) -> ParserRes<P::Output>
+where
+    P: Parser,
+{
+    let ((), rest) = whitespace(input)?;
+    parser.parse(&rest)
+}
+
+macro_rules! impl_parse_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Parse for $t {
+                fn parse_value(input: &Input) -> ParserRes<Self> {
+                    skip_whitespace(input, integer::<$t>())
+                }
+            }
+        )*
+    };
+}
+
+impl_parse_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl Parse for f32 {
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        skip_whitespace(input, FloatParser::<f32>::new())
+    }
+}
+
+impl Parse for f64 {
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        skip_whitespace(input, FloatParser::<f64>::new())
+    }
+}
+
+impl Parse for bool {
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        let boolean = keyword("true")
+            .with_mapping(&|_| true)
+            .otherwise(keyword("false").with_mapping(&|_| false));
+        skip_whitespace(input, boolean)
+    }
+}
+
+impl Parse for String {
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        skip_whitespace(input, StringParser::new())
+    }
+}
+
+impl<T> Parse for Box<T>
+where
+    T: Parse,
+{
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        value::<T>().with_mapping(&Box::new).parse(input)
+    }
+}
+
+impl<T> Parse for Option<T>
+where
+    T: Parse,
+{
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        optional(value::<T>()).parse(input)
+    }
+}
+
+impl<T> Parse for Vec<T>
+where
+    T: Parse,
+{
+    fn parse_value(input: &Input) -> ParserRes<Self> {
+        RepeatParser::new(value::<T>()).minm(0).parse(input)
+    }
+}
+
+#[cfg(test)]
+mod test_derive {
+    use super::{literal, separated, value};
+    use crate::{errors::ParsingErrorKind, inputs::Input, traits::Parser};
+
+    #[test]
+    fn builtin_values() {
+        let (parsed, rest) = value::<Vec<i64>>().parse(&" 1 -2\n3 x".into()).unwrap();
+        assert_eq!(parsed, vec![1, -2, 3]);
+        assert_eq!(rest.source, " x".to_string());
+
+        let (parsed, _) = value::<Option<bool>>().parse(&" false".into()).unwrap();
+        assert_eq!(parsed, Some(false));
+        let (parsed, _) = value::<Option<bool>>().parse(&"falsey".into()).unwrap();
+        assert_eq!(parsed, None);
+
+        let (parsed, _) = value::<Box<String>>().parse(&r#"  "a\"b""#.into()).unwrap();
+        assert_eq!(*parsed, "a\"b".to_string());
+        let (parsed, _) = value::<f32>().parse(&"\t1.5e1".into()).unwrap();
+        assert_eq!(parsed, 15.0);
+    }
+
+    #[test]
+    fn literals_and_separators() {
+        assert!(literal(&" let x".into(), "let").is_ok());
+        assert!(literal(&" letter".into(), "let").is_err());
+        let (_, rest) = literal(&"  ->x".into(), "->").unwrap();
+        assert_eq!(rest.source, "x".to_string());
+
+        let (parsed, rest) = separated::<u8>(&"1 , 2,3]".into(), ",").unwrap();
+        assert_eq!(parsed, vec![1, 2, 3]);
+        assert_eq!(rest.source, "]".to_string());
+
+        let (parsed, rest) = separated::<u8>(&" ]".into(), ",").unwrap();
+        assert!(parsed.is_empty());
+        assert_eq!(rest.source, " ]".to_string());
+
+        // A separator must be followed by another value
+        assert!(separated::<u8>(&"1, ]".into(), ",").is_err());
+        // And a first value that fails part of the way through is not an empty list
+        let err = separated::<String>(&r#""unterminated"#.into(), ",").unwrap_err();
+        assert!(matches!(err.kind(), ParsingErrorKind::PatternNotFound(_)));
+    }
+
+    #[test]
+    fn separated_partial_input() {
+        let partial = |source: &str| Input::from(source).partial(true);
+        assert!(separated::<u8>(&partial("1, 2 "), ",").is_err_and(|e| e.is_incomplete()));
+        assert!(separated::<u8>(&partial("1,"), ",").is_err_and(|e| e.is_incomplete()));
+
+        let (parsed, rest) = separated::<u8>(&partial("1, 2 ]"), ",").unwrap();
+        assert_eq!(parsed, vec![1, 2]);
+        assert_eq!(rest.source, " ]".to_string());
+    }
+}
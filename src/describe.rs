@@ -0,0 +1,539 @@
+use std::collections::HashSet;
+
+/// A description of the grammar that some parser accepts, see `Parser::describe`
+///
+/// Combinators describe themselves in terms of the parsers that they are made of, so the
+/// description of a grammar built out of `ParseMatch`, `and_then`, `otherwise`,
+/// `RepeatParser`, ... follows the structure of the code. Parsers that do not describe
+/// themselves are `Opaque`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Description {
+    /// Some exact text
+    Literal(String),
+    /// Any one of a class of tokens, described in words, such as "digit"
+    Class(String),
+    Sequence(Vec<Description>),
+    Choice(Vec<Description>),
+    Repeat {
+        item: Box<Description>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// A rule of the grammar, with its definition. Rules get their own production in EBNF,
+    /// and their own diagram.
+    Rule {
+        name: String,
+        body: Box<Description>,
+    },
+    /// A rule that is defined somewhere else, such as a recursive rule
+    Reference(String),
+    /// Nothing at all
+    Empty,
+    /// A parser that does not describe itself, named after its type
+    Opaque(String),
+}
+
+impl Description {
+    /// `first` followed by `second`, flattening nested sequences
+    pub fn sequence(first: Description, second: Description) -> Self {
+        let mut items = vec![];
+        for d in [first, second] {
+            match d {
+                Description::Sequence(inner) => items.extend(inner),
+                Description::Empty => {}
+                d => items.push(d),
+            }
+        }
+        match items.len() {
+            0 => Description::Empty,
+            1 => items.remove(0),
+            _ => Description::Sequence(items),
+        }
+    }
+
+    /// `first` or `second`, flattening nested choices
+    pub fn choice(first: Description, second: Description) -> Self {
+        let mut options = vec![];
+        for d in [first, second] {
+            match d {
+                Description::Choice(inner) => options.extend(inner),
+                d => options.push(d),
+            }
+        }
+        Description::Choice(options)
+    }
+
+    pub fn repeat(item: Description, min: usize, max: Option<usize>) -> Self {
+        Description::Repeat {
+            item: Box::new(item),
+            min,
+            max,
+        }
+    }
+
+    pub fn rule(name: &str, body: Description) -> Self {
+        Description::Rule {
+            name: name.to_string(),
+            body: Box::new(body),
+        }
+    }
+
+    /// An `Opaque` description named after the type `T`, without its path or generics
+    pub fn opaque<T>() -> Self {
+        let name = std::any::type_name::<T>();
+        let name = name.split('<').next().unwrap_or(name);
+        Description::Opaque(name.rsplit("::").next().unwrap_or(name).to_string())
+    }
+}
+
+/// A set of named rules, that can be rendered as EBNF, or as railroad diagrams
+///
+/// Rules that are nested in the descriptions (see `Parser::named`) are added after the
+/// rules that use them.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::describe::Grammar;
+/// use parlib::parsers::{repeat_p::RepeatParser, ParseMatch, ParseWhile};
+/// use parlib::traits::Parser;
+///
+/// let digits = ParseWhile(|c| c.is_ascii_digit()).named("digits");
+/// let list = ParseMatch("[")
+///     .and_then(digits)
+///     .and_then(RepeatParser::new(ParseMatch(",").and_then(ParseWhile(|c| c.is_ascii_digit()))).minm(0))
+///     .and_then(ParseMatch("]"));
+///
+/// let grammar = Grammar::new().parser("list", &list);
+/// assert_eq!(
+///     grammar.to_ebnf(),
+///     "list = \"[\" , digits , { \",\" , ? character ? , { ? character ? } } , \"]\" ;\n\
+///      digits = ? character ? , { ? character ? } ;\n"
+/// );
+/// assert!(grammar.to_svg().starts_with("<svg"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Grammar {
+    rules: Vec<(String, Description)>,
+}
+
+impl Grammar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule
+    pub fn rule(mut self, name: &str, description: Description) -> Self {
+        let body = match description {
+            Description::Rule { name: inner, body } if inner == name => *body,
+            description => description,
+        };
+        self.rules.push((name.to_string(), body));
+        self
+    }
+
+    /// Add a rule, described by some parser
+    pub fn parser<I, P>(self, name: &str, parser: &P) -> Self
+    where
+        I: crate::inputs::ParserInput,
+        P: crate::traits::Parser<I>,
+    {
+        self.rule(name, parser.describe())
+    }
+
+    /// All of the rules, including the ones that are nested in the descriptions
+    pub fn rules(&self) -> Vec<(String, Description)> {
+        fn nested(d: &Description, found: &mut Vec<(String, Description)>) {
+            match d {
+                Description::Rule { name, body } => {
+                    found.push((name.clone(), (**body).clone()));
+                    nested(body, found);
+                }
+                Description::Sequence(items) | Description::Choice(items) => {
+                    items.iter().for_each(|d| nested(d, found))
+                }
+                Description::Repeat { item, .. } => nested(item, found),
+                _ => {}
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut rules = vec![];
+        let mut queue = self.rules.clone();
+        let mut i = 0;
+        while i < queue.len() {
+            let (name, body) = queue[i].clone();
+            i += 1;
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let mut found = vec![];
+            nested(&body, &mut found);
+            rules.push((name, body));
+            queue.extend(found);
+        }
+        rules
+    }
+
+    /// The grammar in ISO 14977 EBNF, with a production for every rule. Classes and
+    /// opaque parsers are written as special sequences, `? digit ?`.
+    pub fn to_ebnf(&self) -> String {
+        self.rules()
+            .iter()
+            .map(|(name, body)| format!("{name} = {} ;\n", ebnf(body, Precedence::Choice)))
+            .collect()
+    }
+
+    /// The grammar as an SVG document, with a railroad diagram for every rule
+    pub fn to_svg(&self) -> String {
+        let margin = 20;
+        let mut width = 0;
+        let mut y = margin;
+        let mut body = String::new();
+        for (name, description) in self.rules() {
+            let diagram = railroad(&description);
+            let title = y + 14;
+            let baseline = title + 10 + diagram.up;
+            let end = margin + 10 + diagram.width;
+            body.push_str(&format!(
+                "<text class=\"rule-name\" x=\"{margin}\" y=\"{title}\">{}</text>\n",
+                escape_xml(&name)
+            ));
+            body.push_str(&format!(
+                "<path d=\"M{margin} {} v16 M{margin} {baseline} h10 M{end} {baseline} h10 m0 -8 v16\"/>\n",
+                baseline - 8
+            ));
+            body.push_str(&place(&diagram, margin + 10, baseline));
+            body.push('\n');
+            width = width.max(end + 10 + margin);
+            y = baseline + diagram.down + margin;
+        }
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" class=\"railroad\" width=\"{width}\" height=\"{y}\" viewBox=\"0 0 {width} {y}\">\n\
+             <style>path {{ stroke: #333; stroke-width: 2; fill: none }} \
+             rect {{ stroke: #333; stroke-width: 2; fill: #fff }} \
+             .terminal rect {{ fill: #e6f2ff }} \
+             text {{ font-family: monospace; font-size: 14px }} \
+             .rule-name {{ font-weight: bold }}</style>\n\
+             {body}</svg>\n"
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Choice,
+    Sequence,
+    Atom,
+}
+
+fn ebnf(d: &Description, context: Precedence) -> String {
+    let (text, precedence) = match d {
+        Description::Literal(text) => (quote(text), Precedence::Atom),
+        Description::Class(label) => (format!("? {label} ?"), Precedence::Atom),
+        Description::Opaque(name) => (format!("? {name} ?"), Precedence::Atom),
+        Description::Rule { name, .. } | Description::Reference(name) => {
+            (name.clone(), Precedence::Atom)
+        }
+        Description::Empty => ("\"\"".to_string(), Precedence::Atom),
+        Description::Sequence(items) => {
+            let items = items
+                .iter()
+                .map(|d| ebnf(d, Precedence::Sequence))
+                .collect::<Vec<_>>();
+            (items.join(" , "), Precedence::Sequence)
+        }
+        Description::Choice(options) => {
+            let options = options
+                .iter()
+                .map(|d| ebnf(d, Precedence::Sequence))
+                .collect::<Vec<_>>();
+            (options.join(" | "), Precedence::Choice)
+        }
+        Description::Repeat { item, min, max } => {
+            let inner = ebnf(item, Precedence::Choice);
+            let atom = ebnf(item, Precedence::Atom);
+            let required = match min {
+                0 => None,
+                1 => Some(atom.clone()),
+                n => Some(format!("{n} * {atom}")),
+            };
+            let optional = match max {
+                None => Some(format!("{{ {inner} }}")),
+                Some(max) if max <= min => None,
+                Some(max) if max - min == 1 => Some(format!("[ {inner} ]")),
+                Some(max) => Some(format!("{} * [ {inner} ]", max - min)),
+            };
+            match (required, optional) {
+                (Some(required), Some(optional)) => {
+                    (format!("{required} , {optional}"), Precedence::Sequence)
+                }
+                (Some(only), None) | (None, Some(only)) => {
+                    let precedence = if only.contains(" * ") {
+                        Precedence::Sequence
+                    } else {
+                        Precedence::Atom
+                    };
+                    (only, precedence)
+                }
+                (None, None) => ("\"\"".to_string(), Precedence::Atom),
+            }
+        }
+    };
+
+    if precedence < context {
+        format!("( {text} )")
+    } else {
+        text
+    }
+}
+
+fn quote(text: &str) -> String {
+    if text.chars().any(char::is_control) {
+        format!("? {text:?} ?")
+    } else if !text.contains('"') {
+        format!("\"{text}\"")
+    } else if !text.contains('\'') {
+        format!("'{text}'")
+    } else {
+        format!("? {text:?} ?")
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The radius of the curves between the lines of a diagram
+const ARC: i64 = 10;
+
+/// Part of a railroad diagram. It is entered at (0, 0) and exited at (width, 0), and it
+/// takes up `up` above that line and `down` below it.
+struct Diagram {
+    width: i64,
+    up: i64,
+    down: i64,
+    svg: String,
+}
+
+fn place(diagram: &Diagram, x: i64, y: i64) -> String {
+    format!("<g transform=\"translate({x} {y})\">{}</g>", diagram.svg)
+}
+
+fn text_box(text: &str, class: &str, rounded: bool) -> Diagram {
+    let width = 9 * text.chars().count() as i64 + 20;
+    let rx = if rounded { 12 } else { 0 };
+    Diagram {
+        width,
+        up: 12,
+        down: 12,
+        svg: format!(
+            "<g class=\"{class}\"><rect x=\"0\" y=\"-12\" width=\"{width}\" height=\"24\" rx=\"{rx}\"/>\
+             <text x=\"{}\" y=\"5\" text-anchor=\"middle\">{}</text></g>",
+            width / 2,
+            escape_xml(text)
+        ),
+    }
+}
+
+fn choice(options: &[Diagram]) -> Diagram {
+    let inner = options.iter().map(|d| d.width).max().unwrap_or(0);
+    let width = inner + 4 * ARC;
+    let mut svg = String::new();
+    let mut y = 0;
+    for (i, option) in options.iter().enumerate() {
+        let end = 2 * ARC + option.width;
+        if i == 0 {
+            svg.push_str(&format!(
+                "<path d=\"M0 0 H{} M{end} 0 H{width}\"/>",
+                2 * ARC
+            ));
+        } else {
+            y += options[i - 1].down + ARC + option.up;
+            svg.push_str(&format!(
+                "<path d=\"M0 0 q{ARC} 0 {ARC} {ARC} V{} q0 {ARC} {ARC} {ARC} \
+                 M{end} {y} H{} q{ARC} 0 {ARC} -{ARC} V{ARC} q0 -{ARC} {ARC} -{ARC}\"/>",
+                y - ARC,
+                width - 2 * ARC
+            ));
+        }
+        svg.push_str(&place(option, 2 * ARC, y));
+    }
+    Diagram {
+        width,
+        up: options.first().map_or(0, |d| d.up),
+        down: y + options.last().map_or(0, |d| d.down),
+        svg,
+    }
+}
+
+fn railroad(d: &Description) -> Diagram {
+    match d {
+        Description::Literal(text) => text_box(text, "terminal", true),
+        Description::Class(label) => text_box(label, "class", false),
+        Description::Opaque(name) => text_box(name, "opaque", false),
+        Description::Rule { name, .. } | Description::Reference(name) => {
+            text_box(name, "nonterminal", false)
+        }
+        Description::Empty => Diagram {
+            width: 0,
+            up: 0,
+            down: 0,
+            svg: String::new(),
+        },
+        Description::Sequence(items) => {
+            let mut svg = String::new();
+            let (mut x, mut up, mut down) = (0, 0, 0);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    svg.push_str(&format!("<path d=\"M{x} 0 h{ARC}\"/>"));
+                    x += ARC;
+                }
+                let item = railroad(item);
+                svg.push_str(&place(&item, x, 0));
+                x += item.width;
+                up = up.max(item.up);
+                down = down.max(item.down);
+            }
+            Diagram {
+                width: x,
+                up,
+                down,
+                svg,
+            }
+        }
+        Description::Choice(options) => choice(&options.iter().map(railroad).collect::<Vec<_>>()),
+        Description::Repeat { item, min, max } => {
+            let item = railroad(item);
+            if *min == 0 && *max == Some(1) {
+                return choice(&[railroad(&Description::Empty), item]);
+            }
+
+            let w = item.width;
+            let width = w + 4 * ARC;
+            let loop_y = item.down + ARC;
+            let mut svg = format!(
+                "<path d=\"M0 0 H{} M{} 0 H{width}\"/>\
+                 <path d=\"M{} 0 q{ARC} 0 {ARC} {ARC} V{} q0 {ARC} -{ARC} {ARC} H{} \
+                 q-{ARC} 0 -{ARC} -{ARC} V{ARC} q0 -{ARC} {ARC} -{ARC}\"/>",
+                2 * ARC,
+                2 * ARC + w,
+                2 * ARC + w,
+                loop_y - ARC,
+                2 * ARC
+            );
+            svg.push_str(&place(&item, 2 * ARC, 0));
+
+            let mut up = item.up;
+            if *min == 0 {
+                up += ARC;
+                svg.push_str(&format!(
+                    "<path d=\"M0 0 q{ARC} 0 {ARC} -{ARC} V{} q0 -{ARC} {ARC} -{ARC} H{} \
+                     q{ARC} 0 {ARC} {ARC} V-{ARC} q0 {ARC} {ARC} {ARC}\"/>",
+                    -(up - ARC),
+                    width - 2 * ARC
+                ));
+            }
+
+            let mut down = loop_y;
+            let label = match (min, max) {
+                (0 | 1, None) => None,
+                (min, None) => Some(format!("at least {min} times")),
+                (min, Some(max)) => Some(format!("{min} to {max} times")),
+            };
+            if let Some(label) = label {
+                down += 18;
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{down}\" text-anchor=\"middle\">{label}</text>",
+                    width / 2
+                ));
+            }
+            Diagram {
+                width,
+                up,
+                down,
+                svg,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_describe {
+    use super::{Description, Grammar};
+    use crate::{
+        parsers::{
+            lexeme_p::identifier, number_p::integer, repeat_p::RepeatParser, trie_p::literals,
+            ParseMatch, ParseWhile,
+        },
+        traits::Parser,
+    };
+
+    #[test]
+    fn combinators_describe_themselves() {
+        let p = ParseMatch("a")
+            .and_then(
+                ParseMatch("b")
+                    .otherwise(ParseMatch("c"))
+                    .otherwise(ParseMatch("d")),
+            )
+            .and_then(ParseMatch("e"))
+            .with_error("custom");
+        let literal = |s: &str| Description::Literal(s.to_string());
+        assert_eq!(
+            p.describe(),
+            Description::Sequence(vec![
+                literal("a"),
+                Description::Choice(vec![literal("b"), literal("c"), literal("d")]),
+                literal("e"),
+            ])
+        );
+
+        let repeat = RepeatParser::new(integer::<u8>()).minm(2).maxm(3);
+        assert_eq!(
+            repeat.describe(),
+            Description::repeat(Description::Class("integer".to_string()), 2, Some(3))
+        );
+    }
+
+    #[test]
+    fn ebnf() {
+        let operator = literals(&["+", "-"]).named("operator");
+        let number = ParseWhile(|c| c.is_ascii_digit()).named("number");
+        let sum = number
+            .clone()
+            .and_then(RepeatParser::new(operator.and_then(number)).minm(0))
+            .and_then(RepeatParser::new(ParseMatch(";")).minm(0).maxm(1));
+        let grammar = Grammar::new()
+            .parser("sum", &sum)
+            .rule("quoted", Description::Literal("say \"hi\"".to_string()));
+
+        assert_eq!(
+            grammar.to_ebnf(),
+            "sum = number , { operator , number } , [ \";\" ] ;\n\
+             quoted = 'say \"hi\"' ;\n\
+             number = ? character ? , { ? character ? } ;\n\
+             operator = \"+\" | \"-\" ;\n"
+        );
+    }
+
+    #[test]
+    fn svg() {
+        let p = ParseMatch("if")
+            .and_then(ParseMatch("<a>").otherwise(identifier()))
+            .and_then(RepeatParser::new(ParseMatch("x")).minm(0).maxm(4));
+        let svg = Grammar::new().parser("statement", &p).to_svg();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(">statement</text>"));
+        assert!(svg.contains(">&lt;a&gt;</text>"));
+        assert!(svg.contains(">0 to 4 times</text>"));
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+    }
+}
@@ -1,6 +1,6 @@
-use crate::traits::Parser;
+use crate::{describe::Description, inputs::ParserInput, traits::Parser, type_alias::ParserRes};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParsingError {
     kind: ParsingErrorKind,
     line: usize,
@@ -29,45 +29,107 @@ impl ParsingError {
     pub fn new(kind: ParsingErrorKind, line: usize, col: usize) -> Self {
         ParsingError { kind, line, col }
     }
+
+    pub fn kind(&self) -> &ParsingErrorKind {
+        &self.kind
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    /// The parser reached the end of a partial input, see `Input::partial`
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.kind, ParsingErrorKind::Incomplete(_))
+    }
 }
 
-#[derive(Debug, PartialEq)]
+/// How much more input a parser needs before it can finish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Needed {
+    Unknown,
+    /// At least this many more bytes
+    Size(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParsingErrorKind {
     PatternNotFound(String),
+    /// The input did not match what the parser expected, such as "a digit"
+    Expected(String),
     CannotParseAnEmptyString,
     MappingError(String),
+    /// A backslash inside of a string literal that was not followed by a valid escape
+    InvalidEscapeSequence(String),
+    /// A number was parsed, but it does not fit in the output type
+    NumberOutOfRange(String),
+    /// An identifier was parsed, but it is a reserved word
+    ReservedWord(String),
+    /// The input did not carry any user state of the type that a parser needed
+    MissingState(String),
+    /// A line was not indented as expected, such as "expected indentation of 4 spaces, found 2"
+    IncorrectIndentation(String),
+    /// The parser reached the end of a partial input before it could finish, and it may
+    /// succeed once more input is available
+    Incomplete(Needed),
     /// A custom error that can be added to a parser
     CustomError(String),
 }
 
 /// Add a custom error message to some parser
-pub struct ErrorParser<'a, P>
-where
-    P: Parser,
-{
+pub struct ErrorParser<'a, P> {
     parser: P,
     message: &'a str,
 }
 
-impl<'a, P> ErrorParser<'a, P>
-where
-    P: Parser,
-{
+impl<'a, P> ErrorParser<'a, P> {
     pub fn new(parser: P, message: &'a str) -> Self {
         ErrorParser { parser, message }
     }
 }
 
-impl<'a, P> Parser for ErrorParser<'a, P>
+impl<'a, P, I> Parser<I> for ErrorParser<'a, P>
 where
-    P: Parser,
+    P: Parser<I>,
+    I: ParserInput,
 {
     type Output = P::Output;
 
-    fn parse(&self, input: &crate::inputs::Input) -> crate::type_alias::ParserRes<Self::Output> {
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
         self.parser.parse(input).map_err(|err| {
+            // More input may still make the parser succeed, which is not an error to relabel
+            if err.is_incomplete() {
+                return err;
+            }
             let kind = ParsingErrorKind::CustomError(self.message.to_string());
             ParsingError::new(kind, err.line, err.col)
         })
     }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_errors {
+    use super::{Needed, ParsingErrorKind};
+    use crate::{inputs::Input, parsers::ParseMatch, traits::Parser};
+
+    #[test]
+    fn custom_errors_keep_incomplete() {
+        let hello = ParseMatch("hello").with_error("expected a greeting");
+        let err = hello.parse(&"help".into()).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::CustomError("expected a greeting".to_string())
+        );
+
+        let err = hello.parse(&Input::from("hel").partial(true)).unwrap_err();
+        assert_eq!(err.kind(), &ParsingErrorKind::Incomplete(Needed::Size(2)));
+    }
 }
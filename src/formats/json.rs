@@ -0,0 +1,498 @@
+//! A JSON parser that follows RFC 8259, see `parse` and `JsonParser`
+
+use std::fmt::{Display, Write};
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::{Input, ParserInput},
+    parsers::{
+        char_p::{char, hex_digit, one_of},
+        number_p::float,
+        repeat_p::{count, skip_many},
+        ParseMatch, ParseWhileOrNothing,
+    },
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A JSON value.
+///
+/// The members of an object are kept in the order that they were written in, along with
+/// any duplicated keys, which RFC 8259 allows.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value of a member of an object. If the key is duplicated, the last member with
+    /// that key is used, as most JSON parsers do.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().rev().find(|(k, _)| k == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+}
+
+/// Write the value as compact JSON. Numbers that JSON can not represent, such as the
+/// infinity that `1e999` parses to, are written as `null`.
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) if n.is_finite() => write!(f, "{n}"),
+            Value::Number(_) => write!(f, "null"),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Parse a JSON value, along with any whitespace around it.
+///
+/// Arrays and objects may be nested up to `max_depth` levels (128 by default), so that
+/// deeply nested input gives an error rather than overflowing the stack.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::formats::json::{JsonParser, Value};
+/// use parlib::traits::Parser;
+///
+/// let (parsed, rest) = JsonParser::new().parse(&r#" [1, -2.5e1, "é"] ,"#.into()).unwrap();
+/// assert_eq!(
+///     parsed,
+///     Value::Array(vec![
+///         Value::Number(1.0),
+///         Value::Number(-25.0),
+///         Value::String("é".to_string()),
+///     ])
+/// );
+/// assert_eq!(rest.source, ",".to_string());
+///
+/// assert!(JsonParser::new().max_depth(2).parse(&"[[[]]]".into()).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct JsonParser {
+    max_depth: usize,
+}
+
+impl Default for JsonParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonParser {
+    pub fn new() -> Self {
+        JsonParser { max_depth: 128 }
+    }
+
+    /// How many arrays and objects may be nested inside of each other
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    fn value(&self, input: &Input, depth: usize) -> ParserRes<Value> {
+        let (_, input) = whitespace().parse(input)?;
+        let (value, rest) = match input.source.chars().next() {
+            Some('{') => self.object(&input, depth + 1)?,
+            Some('[') => self.array(&input, depth + 1)?,
+            Some('"') => JsonString.with_mapping(&Value::String).parse(&input)?,
+            Some('-' | '0'..='9') => float().with_mapping(&Value::Number).parse(&input)?,
+            _ => ParseMatch("true")
+                .with_mapping(&|_| Value::Bool(true))
+                .otherwise(ParseMatch("false").with_mapping(&|_| Value::Bool(false)))
+                .otherwise(ParseMatch("null").with_mapping(&|_| Value::Null))
+                .parse(&input)
+                .map_err(|e| match e.is_incomplete() {
+                    true => e,
+                    false => {
+                        let kind = ParsingErrorKind::Expected("a JSON value".to_string());
+                        ParsingError::new(kind, input.line, input.col)
+                    }
+                })?,
+        };
+        let (_, rest) = whitespace().parse(&rest)?;
+        Ok((value, rest))
+    }
+
+    fn array(&self, input: &Input, depth: usize) -> ParserRes<Value> {
+        self.check_depth(input, depth)?;
+        let (_, rest) = char('[').and_then(whitespace()).parse(input)?;
+        let mut items = vec![];
+        if let Ok((_, rest)) = char(']').parse(&rest) {
+            return Ok((Value::Array(items), rest));
+        }
+
+        let mut rest = rest;
+        loop {
+            let (item, next) = self.value(&rest, depth)?;
+            items.push(item);
+            match one_of(",]").label("',' or ']'").parse(&next)? {
+                (',', next) => rest = next,
+                (_, next) => return Ok((Value::Array(items), next)),
+            }
+        }
+    }
+
+    fn object(&self, input: &Input, depth: usize) -> ParserRes<Value> {
+        self.check_depth(input, depth)?;
+        let (_, rest) = char('{').and_then(whitespace()).parse(input)?;
+        let mut members = vec![];
+        if let Ok((_, rest)) = char('}').parse(&rest) {
+            return Ok((Value::Object(members), rest));
+        }
+
+        let mut rest = rest;
+        loop {
+            let (key, next) = JsonString.preceed(whitespace()).parse(&rest)?;
+            let (_, next) = whitespace().and_then(char(':')).parse(&next)?;
+            let (value, next) = self.value(&next, depth)?;
+            members.push((key, value));
+            match one_of(",}").label("',' or '}'").parse(&next)? {
+                (',', next) => rest = next,
+                (_, next) => return Ok((Value::Object(members), next)),
+            }
+        }
+    }
+
+    fn check_depth(&self, input: &Input, depth: usize) -> Result<(), ParsingError> {
+        if depth <= self.max_depth {
+            return Ok(());
+        }
+        let kind = ParsingErrorKind::PatternNotFound(format!(
+            "arrays and objects are nested more than {} levels deep",
+            self.max_depth
+        ));
+        Err(ParsingError::new(kind, input.line, input.col))
+    }
+}
+
+impl Parser for JsonParser {
+    type Output = Value;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        self.value(input, 0)
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("JSON value".to_string())
+    }
+}
+
+/// Parse a JSON value, see `JsonParser`
+pub fn json() -> JsonParser {
+    JsonParser::new()
+}
+
+/// Parse a whole JSON text: a single value, with nothing but whitespace around it
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::formats::json::{parse, Value};
+///
+/// let value = parse(r#"{"name": "parlib", "tags": null}"#).unwrap();
+/// assert_eq!(value.get("name"), Some(&Value::String("parlib".to_string())));
+/// assert_eq!(value.to_string(), r#"{"name":"parlib","tags":null}"#);
+///
+/// let err = parse("[1, 2] 3").unwrap_err();
+/// assert_eq!(err.col(), 7);
+/// ```
+pub fn parse(source: &str) -> Result<Value, ParsingError> {
+    let (value, rest) = json().parse(&Input::from(source))?;
+    if !rest.is_at_end() {
+        let kind = ParsingErrorKind::Expected("the end of the input".to_string());
+        return Err(ParsingError::new(kind, rest.line, rest.col));
+    }
+    Ok(value)
+}
+
+/// Whitespace between tokens, which is only space, tab, line feed and carriage return
+fn whitespace() -> impl Parser<Output = ()> {
+    skip_many(one_of(" \t\n\r")).with_mapping(&|_| ())
+}
+
+/// A string, decoding its escape sequences
+struct JsonString;
+
+impl Parser for JsonString {
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (_, mut rest) = char('"').label("a string").parse(input)?;
+        let mut decoded = String::new();
+        loop {
+            let unescaped = ParseWhileOrNothing(|c| c != '"' && c != '\\' && c >= ' ');
+            let (text, next) = unescaped.parse(&rest)?;
+            decoded.push_str(&text);
+            rest = next;
+
+            let message = match rest.source.chars().next() {
+                Some('"') => return Ok((decoded, rest.char_offset(1))),
+                Some('\\') => {
+                    let (c, next) = escape(&rest)?;
+                    decoded.push(c);
+                    rest = next;
+                    continue;
+                }
+                Some(_) => "control characters in a string must be escaped",
+                None if rest.is_partial() => return Err(rest.incomplete(Needed::Unknown)),
+                None => "did not find the closing quote",
+            };
+            let kind = ParsingErrorKind::PatternNotFound(message.to_string());
+            return Err(ParsingError::new(kind, rest.line, rest.col));
+        }
+    }
+}
+
+/// An escape sequence, starting with its backslash. A `\u` escape of a UTF-16 high
+/// surrogate must be followed by one of a low surrogate.
+fn escape(input: &Input) -> ParserRes<char> {
+    let invalid = |message: String| {
+        let kind = ParsingErrorKind::InvalidEscapeSequence(message);
+        ParsingError::new(kind, input.line, input.col)
+    };
+    let (c, rest) = one_of("\"\\/bfnrtu")
+        .label("an escape sequence")
+        .preceed(char('\\'))
+        .parse(input)
+        .map_err(|e| match e.is_incomplete() {
+            true => e,
+            false => invalid("expected one of \\\" \\\\ \\/ \\b \\f \\n \\r \\t \\u".to_string()),
+        })?;
+    let decoded = match c {
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'u' => {
+            let (high, rest) = hex4().parse(&rest)?;
+            if !(0xD800..0xE000).contains(&high) {
+                return Ok((char::from_u32(high).unwrap_or_default(), rest));
+            }
+            if high >= 0xDC00 {
+                return Err(invalid(format!("\\u{high:04X} is a lone low surrogate")));
+            }
+            let low = hex4().preceed(ParseMatch("\\u")).parse(&rest);
+            return match low {
+                Ok((low, rest)) if (0xDC00..0xE000).contains(&low) => {
+                    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                    Ok((char::from_u32(code).unwrap_or_default(), rest))
+                }
+                Err(e) if e.is_incomplete() => Err(e),
+                _ => Err(invalid(format!(
+                    "\\u{high:04X} must be followed by a low surrogate"
+                ))),
+            };
+        }
+        c => c,
+    };
+    Ok((decoded, rest))
+}
+
+/// The 4 hex digits of a `\u` escape
+fn hex4() -> impl Parser<Output = u32> {
+    count(4, hex_digit()).with_mapping(&|digits| {
+        digits
+            .iter()
+            .fold(0, |code, d| code * 16 + d.to_digit(16).unwrap_or_default())
+    })
+}
+
+#[cfg(test)]
+mod test_json {
+    use super::{parse, JsonParser, Value};
+    use crate::{errors::ParsingErrorKind, inputs::Input, traits::Parser};
+
+    #[test]
+    fn values() {
+        let value = parse(
+            r#" {
+                "null": null, "bools": [true, false],
+                "numbers": [0, -0, 12, -3.25, 1e3, 2.5E-1, 1e999],
+                "strings": ["", "\"\\\/\b\f\n\r\t", "é😀", "€"],
+                "nested": {"a": [{}], "a": []}
+            } "#,
+        )
+        .unwrap();
+        assert_eq!(value.get("null"), Some(&Value::Null));
+        assert_eq!(
+            value.get("bools"),
+            Some(&Value::Array(vec![Value::Bool(true), Value::Bool(false)]))
+        );
+
+        let numbers = [0.0, -0.0, 12.0, -3.25, 1000.0, 0.25, f64::INFINITY];
+        assert_eq!(
+            value.get("numbers"),
+            Some(&Value::Array(numbers.map(Value::Number).to_vec()))
+        );
+
+        let strings = ["", "\"\\/\u{8}\u{c}\n\r\t", "é\u{1F600}", "€"];
+        assert_eq!(
+            value.get("strings"),
+            Some(&Value::Array(
+                strings.map(|s| Value::String(s.to_string())).to_vec()
+            ))
+        );
+
+        // Duplicated keys are kept, and the last one is used
+        let Some(Value::Object(nested)) = value.get("nested") else {
+            panic!("expected an object");
+        };
+        assert_eq!(nested.len(), 2);
+        assert_eq!(
+            value.get("nested").unwrap().get("a"),
+            Some(&Value::Array(vec![]))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse("[1, 2,]").unwrap_err();
+        assert_eq!(err.col(), 6);
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("a JSON value".to_string())
+        );
+
+        let err = parse("{\"a\": 1\n \"b\": 2}").unwrap_err();
+        assert_eq!((err.line(), err.col()), (1, 1));
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("',' or '}'".to_string())
+        );
+
+        let err = parse(r#"["\x41"]"#).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParsingErrorKind::InvalidEscapeSequence(_)
+        ));
+        assert_eq!(err.col(), 2);
+        let err = parse(r#"["\uDE00"]"#).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParsingErrorKind::InvalidEscapeSequence(_)
+        ));
+
+        // Things that other formats allow
+        for source in [
+            "[01]",
+            "[.5]",
+            "[+1]",
+            "['a']",
+            "{a: 1}",
+            "[1,]",
+            "\"a\tb\"",
+            "[\u{a0}1]",
+        ] {
+            assert!(parse(source).is_err(), "{source}");
+        }
+
+        let deep = "[".repeat(10_000);
+        assert!(parse(&deep).is_err());
+        let nested = format!("{}{}", "[".repeat(5), "]".repeat(5));
+        assert!(JsonParser::new()
+            .max_depth(5)
+            .parse(&nested.as_str().into())
+            .is_ok());
+        assert!(JsonParser::new()
+            .max_depth(4)
+            .parse(&nested.as_str().into())
+            .is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let source = r#"{"a":[1,-2.5,true,null],"b\n\"":"\u0001é","c":{}}"#;
+        let value = parse(source).unwrap();
+        assert_eq!(value.to_string(), source);
+        assert_eq!(parse(&value.to_string()).unwrap(), value);
+    }
+
+    #[test]
+    fn partial_input() {
+        // The rest of each value may still follow in the next part of the input
+        for source in [
+            "",
+            "[tr",
+            "{\"a\": nu",
+            "[\"ab",
+            "[\"a\\",
+            "[\"\\u00",
+            "[\"\\uD83D",
+            "[\"\\uD83D\\uDE",
+            "[1, 2",
+        ] {
+            let input = Input::from(source).partial(true);
+            assert!(
+                JsonParser::new()
+                    .parse(&input)
+                    .is_err_and(|e| e.is_incomplete()),
+                "{source}"
+            );
+        }
+
+        for source in ["[tru]", "[\"\\x\"]", "[\"\\uD83Dx\"]"] {
+            let input = Input::from(source).partial(true);
+            assert!(
+                JsonParser::new()
+                    .parse(&input)
+                    .is_err_and(|e| !e.is_incomplete()),
+                "{source}"
+            );
+        }
+        let input = Input::from("[1, \"a\"],").partial(true);
+        let (value, rest) = JsonParser::new().parse(&input).unwrap();
+        assert_eq!(
+            value,
+            Value::Array(vec![Value::Number(1.0), Value::String("a".to_string())])
+        );
+        assert_eq!(rest.source, ",");
+    }
+}
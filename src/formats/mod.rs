@@ -0,0 +1,5 @@
+//! Parsers for common data formats, made with the combinators of this library. Each format
+//! is behind a cargo feature of the same name.
+
+#[cfg(feature = "json")]
+pub mod json;
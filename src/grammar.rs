@@ -0,0 +1,486 @@
+//! A declarative way to write a grammar, see `grammar!`
+
+use std::{any::TypeId, cell::RefCell, fmt::Debug};
+
+use crate::{
+    describe::Description,
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Write a grammar as a list of rules, each of which becomes a unit struct that implements
+/// `Parser`. Rules can refer to each other, and to themselves, by name.
+///
+/// ```text
+/// grammar! {
+///     skip = <parser>;                        // optional, run before every item
+///
+///     <Name> -> <Output> = <alternative> | <alternative> ... ;
+///     ...
+/// }
+/// ```
+///
+/// The alternatives of a rule are tried in order, and the first one that succeeds is used.
+/// An alternative is a sequence of items, optionally followed by an action block,
+/// `=> { ... }`, which builds the output of the rule. Without an action, the output is
+/// the output of the last item. An item is one of:
+///
+/// - a string literal, parsed with `ParseMatch`
+/// - the name of a rule, or of any other unit struct that is a parser
+/// - any parser expression, in braces: `{ integer::<i64>() }`
+/// - a group of items in parentheses, which may have its own action: `("," v: Value => { v })`
+///
+/// followed by an optional `*` (zero or more, into a `Vec`), `+` (one or more) or `?`
+/// (an `Option`). Items can be bound to a name for the action block with `name: item`.
+///
+/// A rule describes itself by its alternatives, leaving out the skip parser, so a
+/// `describe::Grammar` of a rule has a production for it and for every rule that it uses.
+///
+/// If none of the alternatives of a rule make any progress, the error is
+/// `Expected(<Name>)`, so rule names double as labels in errors. Otherwise the error that
+/// got the furthest is kept.
+///
+/// Rules may not be left recursive, as in `Sum -> i64 = Sum "+" Num`, since that would
+/// never stop.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::errors::ParsingErrorKind;
+/// use parlib::grammar;
+/// use parlib::parsers::{lexeme_p::TokenParser, number_p::integer};
+/// use parlib::traits::Parser;
+///
+/// grammar! {
+///     skip = TokenParser::new().whitespace();
+///
+///     Sum -> i64 = first: Product rest: ("+" Product)* => { first + rest.iter().sum::<i64>() };
+///     Product -> i64 = first: Atom rest: ("*" Atom)* => { first * rest.iter().product::<i64>() };
+///     Atom -> i64 = { integer::<i64>() } | "(" inner: Sum ")" => { inner };
+/// }
+///
+/// let (parsed, _) = Sum.parse(&"2 * (3 + 4) + 1".into()).unwrap();
+/// assert_eq!(parsed, 15);
+///
+/// let err = Sum.parse(&"(x)".into()).unwrap_err();
+/// assert_eq!(err.kind(), &ParsingErrorKind::Expected("Sum".to_string()));
+/// assert_eq!(err.col(), 1);
+/// ```
+#[macro_export]
+macro_rules! grammar {
+    // Split the rules on `;`
+    (@rules $skip:tt) => {};
+    (@rules $skip:tt $(#[$meta:meta])* $vis:vis $name:ident -> $out:ty = $($rest:tt)*) => {
+        $crate::grammar!(@body $skip [$(#[$meta])* $vis $name $out] [] $($rest)*);
+    };
+    (@body $skip:tt $head:tt [$($body:tt)*] ; $($rest:tt)*) => {
+        $crate::grammar!(@rule $skip $head $($body)*);
+        $crate::grammar!(@rules $skip $($rest)*);
+    };
+    // The tokens are moved a few at a time, since every step is another level of
+    // recursion, and the rules after this one are expanded inside of this one
+    (@body $skip:tt $head:tt [$($body:tt)*] $a:tt ; $($rest:tt)*) => {
+        $crate::grammar!(@body $skip $head [$($body)* $a] ; $($rest)*);
+    };
+    (@body $skip:tt $head:tt [$($body:tt)*] $a:tt $b:tt ; $($rest:tt)*) => {
+        $crate::grammar!(@body $skip $head [$($body)* $a $b] ; $($rest)*);
+    };
+    (@body $skip:tt $head:tt [$($body:tt)*] $a:tt $b:tt $c:tt ; $($rest:tt)*) => {
+        $crate::grammar!(@body $skip $head [$($body)* $a $b $c] ; $($rest)*);
+    };
+    (@body $skip:tt $head:tt [$($body:tt)*] $a:tt $b:tt $c:tt $d:tt $($rest:tt)*) => {
+        $crate::grammar!(@body $skip $head [$($body)* $a $b $c $d] $($rest)*);
+    };
+
+    (@rule $skip:tt [$(#[$meta:meta])* $vis:vis $name:ident $out:ty] $($body:tt)*) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name;
+
+        impl $crate::traits::Parser for $name {
+            type Output = $out;
+            fn parse(
+                &self,
+                input: &$crate::inputs::Input,
+            ) -> $crate::type_alias::ParserRes<Self::Output> {
+                let mut best = None;
+                $crate::grammar!(@alts $skip input best rest [$out] [] $($body)*);
+                Err($crate::grammar::rule_error(stringify!($name), input, best))
+            }
+
+            fn describe(&self) -> $crate::describe::Description {
+                $crate::grammar::describe_rule::<Self>(stringify!($name), || {
+                    $crate::grammar!(@describe_alts [] [] $($body)*)
+                })
+            }
+        }
+    };
+
+    // Split the alternatives on `|`
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*]) => {
+        $crate::grammar!(@alt $skip $input $best $rest $out $($alt)*);
+    };
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] | $($more:tt)*) => {
+        $crate::grammar!(@alt $skip $input $best $rest $out $($alt)*);
+        $crate::grammar!(@alts $skip $input $best $rest $out [] $($more)*);
+    };
+    // Like the body of a rule, an alternative is moved a few tokens at a time
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] $a:tt | $($more:tt)*) => {
+        $crate::grammar!(@alts $skip $input $best $rest $out [$($alt)* $a] | $($more)*);
+    };
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] $a:tt $b:tt | $($more:tt)*) => {
+        $crate::grammar!(@alts $skip $input $best $rest $out [$($alt)* $a $b] | $($more)*);
+    };
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] $a:tt $b:tt $c:tt | $($more:tt)*) => {
+        $crate::grammar!(@alts $skip $input $best $rest $out [$($alt)* $a $b $c] | $($more)*);
+    };
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] $a:tt $b:tt $c:tt $d:tt $($more:tt)*) => {
+        $crate::grammar!(@alts $skip $input $best $rest $out [$($alt)* $a $b $c $d] $($more)*);
+    };
+    (@alts $skip:tt $input:ident $best:ident $rest:ident $out:tt [$($alt:tt)*] $($last:tt)+) => {
+        $crate::grammar!(@alt $skip $input $best $rest $out $($alt)* $($last)+);
+    };
+
+    // The description of a rule is split into alternatives in the same way
+    (@describe_alts [$($done:tt)*] [$($alt:tt)*]) => {
+        vec![$($done)* $crate::grammar!(@describe_seq $($alt)*)]
+    };
+    (@describe_alts [$($done:tt)*] [$($alt:tt)*] | $($more:tt)*) => {
+        $crate::grammar!(@describe_alts
+            [$($done)* $crate::grammar!(@describe_seq $($alt)*),] [] $($more)*)
+    };
+    (@describe_alts $done:tt [$($alt:tt)*] $a:tt | $($more:tt)*) => {
+        $crate::grammar!(@describe_alts $done [$($alt)* $a] | $($more)*)
+    };
+    (@describe_alts $done:tt [$($alt:tt)*] $a:tt $b:tt | $($more:tt)*) => {
+        $crate::grammar!(@describe_alts $done [$($alt)* $a $b] | $($more)*)
+    };
+    (@describe_alts $done:tt [$($alt:tt)*] $a:tt $b:tt $c:tt | $($more:tt)*) => {
+        $crate::grammar!(@describe_alts $done [$($alt)* $a $b $c] | $($more)*)
+    };
+    (@describe_alts $done:tt [$($alt:tt)*] $a:tt $b:tt $c:tt $d:tt $($more:tt)*) => {
+        $crate::grammar!(@describe_alts $done [$($alt)* $a $b $c $d] $($more)*)
+    };
+    (@describe_alts $done:tt [$($alt:tt)*] $($last:tt)+) => {
+        $crate::grammar!(@describe_alts $done [$($alt)* $($last)+])
+    };
+
+    // The items of an alternative, without the skip parser, bindings and actions
+    (@describe_seq $(=> $action:block)?) => {
+        $crate::describe::Description::Empty
+    };
+    (@describe_seq $bind:ident : $($t:tt)*) => {
+        $crate::grammar!(@describe_seq $($t)*)
+    };
+    (@describe_seq $lit:literal $($t:tt)*) => {
+        $crate::grammar!(@describe_suffix
+            [$crate::describe::Description::Literal($lit.to_string())] $($t)*)
+    };
+    (@describe_seq $rule:ident $($t:tt)*) => {
+        $crate::grammar!(@describe_suffix
+            [$crate::traits::Parser::<$crate::inputs::Input>::describe(&$rule)] $($t)*)
+    };
+    (@describe_seq { $($p:tt)* } $($t:tt)*) => {
+        $crate::grammar!(@describe_suffix
+            [$crate::traits::Parser::<$crate::inputs::Input>::describe(&{ $($p)* })] $($t)*)
+    };
+    (@describe_seq ($($group:tt)*) $($t:tt)*) => {
+        $crate::grammar!(@describe_suffix [$crate::grammar!(@describe_seq $($group)*)] $($t)*)
+    };
+
+    (@describe_suffix [$($d:tt)*] * $($t:tt)*) => {
+        $crate::grammar!(@describe_then [$crate::describe::Description::repeat($($d)*, 0, None)] $($t)*)
+    };
+    (@describe_suffix [$($d:tt)*] + $($t:tt)*) => {
+        $crate::grammar!(@describe_then [$crate::describe::Description::repeat($($d)*, 1, None)] $($t)*)
+    };
+    (@describe_suffix [$($d:tt)*] ? $($t:tt)*) => {
+        $crate::grammar!(@describe_then [$crate::describe::Description::repeat($($d)*, 0, Some(1))] $($t)*)
+    };
+    (@describe_suffix [$($d:tt)*] $($t:tt)*) => {
+        $crate::grammar!(@describe_then [$($d)*] $($t)*)
+    };
+    (@describe_then [$($d:tt)*] $($t:tt)*) => {
+        $crate::describe::Description::sequence($($d)*, $crate::grammar!(@describe_seq $($t)*))
+    };
+
+    (@alt $skip:tt $input:ident $best:ident $rest:ident [$out:ty] $($items:tt)*) => {
+        let attempt = (|| -> $crate::type_alias::ParserRes<$out> {
+            let $rest = $input.clone();
+            $crate::grammar!(@seq $skip $rest $($items)*)
+        })();
+        match attempt {
+            Ok(parsed) => return Ok(parsed),
+            Err(e) if e.is_incomplete() => return Err(e),
+            Err(e) => $crate::grammar::keep_furthest(&mut $best, e),
+        }
+    };
+
+    // The items of an alternative, parsed one after another from `$rest`
+    (@seq $skip:tt $rest:ident => $action:block) => {
+        Ok(($action, $rest))
+    };
+    (@seq $skip:tt $rest:ident $bind:ident : $($t:tt)*) => {
+        $crate::grammar!(@item $skip $rest [$bind] $($t)*)
+    };
+    (@seq $skip:tt $rest:ident $($t:tt)+) => {
+        $crate::grammar!(@item $skip $rest [] $($t)*)
+    };
+
+    (@item $skip:tt $rest:ident $bind:tt $lit:literal $($t:tt)*) => {
+        $crate::grammar!(@suffix $skip $rest $bind [$crate::parsers::ParseMatch($lit)] $($t)*)
+    };
+    (@item $skip:tt $rest:ident $bind:tt $rule:ident $($t:tt)*) => {
+        $crate::grammar!(@suffix $skip $rest $bind [$rule] $($t)*)
+    };
+    (@item $skip:tt $rest:ident $bind:tt { $($p:tt)* } $($t:tt)*) => {
+        $crate::grammar!(@suffix $skip $rest $bind [{ $($p)* }] $($t)*)
+    };
+    (@item $skip:tt $rest:ident $bind:tt ($($group:tt)*) $($t:tt)*) => {
+        $crate::grammar!(@suffix $skip $rest $bind [$crate::grammar::Group(
+            |$rest: &$crate::inputs::Input| -> $crate::type_alias::ParserRes<_> {
+                let $rest = $rest.clone();
+                $crate::grammar!(@seq $skip $rest $($group)*)
+            }
+        )] $($t)*)
+    };
+
+    (@suffix $skip:tt $rest:ident $bind:tt [$($p:tt)*] * $($t:tt)*) => {
+        $crate::grammar!(@parse $skip $rest $bind
+            [$crate::parsers::repeat_p::RepeatParser::new($($p)*).minm(0)] $($t)*)
+    };
+    (@suffix $skip:tt $rest:ident $bind:tt [$($p:tt)*] + $($t:tt)*) => {
+        $crate::grammar!(@parse $skip $rest $bind
+            [$crate::parsers::repeat_p::RepeatParser::new($($p)*)] $($t)*)
+    };
+    (@suffix $skip:tt $rest:ident $bind:tt [$($p:tt)*] ? $($t:tt)*) => {
+        $crate::grammar!(@parse $skip $rest $bind
+            [$crate::parsers::repeat_p::optional($($p)*)] $($t)*)
+    };
+    (@suffix $skip:tt $rest:ident $bind:tt [$($p:tt)*] $($t:tt)*) => {
+        $crate::grammar!(@parse $skip $rest $bind [$($p)*] $($t)*)
+    };
+
+    (@parse [$($skip:tt)*] $rest:ident [$($bind:ident)?] [$($p:tt)*]) => {{
+        let (_, $rest) = $crate::traits::Parser::parse(&$($skip)*, &$rest)?;
+        let (parsed, $rest) = $crate::traits::Parser::parse(&$($p)*, &$rest)?;
+        Ok((parsed, $rest))
+    }};
+    (@parse [$($skip:tt)*] $rest:ident [$bind:ident] [$($p:tt)*] $($t:tt)+) => {{
+        let (_, $rest) = $crate::traits::Parser::parse(&$($skip)*, &$rest)?;
+        let ($bind, $rest) = $crate::traits::Parser::parse(&$($p)*, &$rest)?;
+        $crate::grammar!(@seq [$($skip)*] $rest $($t)*)
+    }};
+    (@parse [$($skip:tt)*] $rest:ident [] [$($p:tt)*] $($t:tt)+) => {{
+        let (_, $rest) = $crate::traits::Parser::parse(&$($skip)*, &$rest)?;
+        let (_, $rest) = $crate::traits::Parser::parse(&$($p)*, &$rest)?;
+        $crate::grammar!(@seq [$($skip)*] $rest $($t)*)
+    }};
+
+    (skip = $skip:expr; $($rules:tt)*) => {
+        $crate::grammar!(@rules [($skip)] $($rules)*);
+    };
+    ($($rules:tt)*) => {
+        $crate::grammar!(@rules [$crate::parsers::iter_p::NoSeparator] $($rules)*);
+    };
+}
+
+/// A group of items inside of a rule, see `grammar!`
+#[doc(hidden)]
+pub struct Group<F>(pub F);
+
+impl<F, O> Parser for Group<F>
+where
+    F: Fn(&Input) -> ParserRes<O>,
+    O: Debug,
+{
+    type Output = O;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        (self.0)(input)
+    }
+}
+
+thread_local! {
+    /// The rules of `grammar!`s that are being described, see `describe_rule`
+    static DESCRIBING: RefCell<Vec<TypeId>> = const { RefCell::new(vec![]) };
+}
+
+/// Describe a rule of a `grammar!` by its alternatives. The rules that it uses are
+/// described inside of it, except for the rules that are already being described, such
+/// as the rule itself, which are references.
+#[doc(hidden)]
+pub fn describe_rule<R>(name: &str, alternatives: impl FnOnce() -> Vec<Description>) -> Description
+where
+    R: 'static,
+{
+    let rule = TypeId::of::<R>();
+    if DESCRIBING.with_borrow(|rules| rules.contains(&rule)) {
+        return Description::Reference(name.to_string());
+    }
+
+    DESCRIBING.with_borrow_mut(|rules| rules.push(rule));
+    let mut alternatives = alternatives();
+    DESCRIBING.with_borrow_mut(|rules| rules.pop());
+    let body = match alternatives.len() {
+        1 => alternatives.remove(0),
+        _ => Description::Choice(alternatives),
+    };
+    Description::rule(name, body)
+}
+
+/// Keep whichever error got the furthest, see `grammar!`
+#[doc(hidden)]
+pub fn keep_furthest(best: &mut Option<ParsingError>, error: ParsingError) {
+    if best.as_ref().is_none_or(|best| error > *best) {
+        *best = Some(error);
+    }
+}
+
+/// The error of a rule of a `grammar!` whose alternatives all failed
+#[doc(hidden)]
+pub fn rule_error(name: &str, input: &Input, best: Option<ParsingError>) -> ParsingError {
+    match best {
+        Some(best) if (best.line(), best.col()) != (input.line, input.col) => best,
+        _ => {
+            let kind = ParsingErrorKind::Expected(name.to_string());
+            ParsingError::new(kind, input.line, input.col)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_grammar {
+    use crate::{
+        describe::{Description, Grammar},
+        errors::ParsingErrorKind,
+        parsers::{
+            lexeme_p::TokenParser, number_p::integer, string_p::StringParser, ParseMatch,
+            ParseWhile,
+        },
+        traits::Parser,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Null,
+        Number(i64),
+        Str(String),
+        List(Vec<Value>),
+    }
+
+    grammar! {
+        skip = TokenParser::new().whitespace();
+
+        Json -> Value = "null" => { Value::Null }
+            | n: { integer::<i64>() } => { Value::Number(n) }
+            | s: { StringParser::new() } => { Value::Str(s) }
+            | "[" items: Items? "]" => { Value::List(items.unwrap_or_default()) };
+        Items -> Vec<Value> = first: Json rest: ("," v: Json => { v })* => {
+            let mut items = vec![first];
+            items.extend(rest);
+            items
+        };
+    }
+
+    #[test]
+    fn recursive_rules() {
+        let (parsed, rest) = Json.parse(&r#" [1, "two", [null, []]]"#.into()).unwrap();
+        assert_eq!(
+            parsed,
+            Value::List(vec![
+                Value::Number(1),
+                Value::Str("two".to_string()),
+                Value::List(vec![Value::Null, Value::List(vec![])]),
+            ])
+        );
+        assert!(rest.source.is_empty());
+    }
+
+    #[test]
+    fn rule_names_as_labels() {
+        let err = Json.parse(&"true".into()).unwrap_err();
+        assert_eq!(err.kind(), &ParsingErrorKind::Expected("Json".to_string()));
+        assert_eq!(err.col(), 0);
+
+        // Once a rule has made progress, the error that got the furthest is kept
+        let err = Json.parse(&"[1, 2 3]".into()).unwrap_err();
+        assert_eq!((err.line(), err.col()), (0, 6));
+        assert!(!matches!(err.kind(), ParsingErrorKind::Expected(_)));
+    }
+
+    #[test]
+    fn describe_rules() {
+        // The rules that a rule uses get their own productions, and recursion is a reference
+        let grammar = Grammar::new().parser("Json", &Json);
+        assert_eq!(
+            grammar.to_ebnf(),
+            "Json = \"null\" | ? integer ? | ? string ? | \"[\" , [ Items ] , \"]\" ;\n\
+             Items = Json , { \",\" , Json } ;\n"
+        );
+        assert!(matches!(Items.describe(), Description::Rule { name, .. } if name == "Items"));
+
+        let grammar = Grammar::new().parser("Words", &Words);
+        assert_eq!(
+            grammar.to_ebnf(),
+            "Words = Word , ( \" \" , Word ) , { \" \" , Word } ;\n\
+             Word = ? character ? , { ? character ? } ;\n"
+        );
+    }
+
+    grammar! {
+        Word -> String = letters: { ParseWhile(char::is_alphabetic) } ;
+        Words -> Vec<String> = first: Word rest: (" " Word)+ => {
+            let mut words = vec![first];
+            words.extend(rest);
+            words
+        };
+        Greeting -> (Option<String>, String) = polite: { ParseMatch("please ") }? w: Word => { (polite, w) };
+    }
+
+    #[test]
+    fn without_skip() {
+        let (parsed, _) = Words.parse(&"a bc d".into()).unwrap();
+        assert_eq!(parsed, vec!["a", "bc", "d"]);
+        assert!(Words.parse(&"a".into()).is_err());
+        assert!(Words.parse(&"a  b".into()).is_err());
+
+        let (parsed, _) = Greeting.parse(&"please help".into()).unwrap();
+        assert_eq!(parsed, (Some("please ".to_string()), "help".to_string()));
+        let (parsed, _) = Greeting.parse(&"help".into()).unwrap();
+        assert_eq!(parsed, (None, "help".to_string()));
+    }
+
+    // Long enough that moving one token of a rule at a time hits the recursion limit
+    grammar! {
+        skip = TokenParser::new().whitespace();
+
+        Date -> (u32, u32, u32, u32) =
+            day: Weekday "," date: { integer::<u32>() } month: Month year: { integer::<u32>() }
+            => { (day, date, month, year) };
+        Month -> u32 = "january" => { 1 } | "jan" => { 1 }
+            | "february" => { 2 } | "feb" => { 2 }
+            | "march" => { 3 } | "mar" => { 3 }
+            | "april" => { 4 } | "apr" => { 4 }
+            | "may" => { 5 } | "june" => { 6 }
+            | "jun" => { 6 } | "july" => { 7 }
+            | "jul" => { 7 } | "august" => { 8 }
+            | "aug" => { 8 } | "september" => { 9 }
+            | "sep" => { 9 } | "october" => { 10 }
+            | "oct" => { 10 } | "november" => { 11 }
+            | "nov" => { 11 } | "december" => { 12 }
+            | "dec" => { 12 };
+        Weekday -> u32 = "monday" => { 1 } | "tuesday" => { 2 } | "wednesday" => { 3 }
+            | "thursday" => { 4 } | "friday" => { 5 } | "saturday" => { 6 }
+            | "sunday" => { 7 };
+    }
+
+    #[test]
+    fn long_rules() {
+        let (parsed, _) = Date.parse(&"friday, 13 sep 2024".into()).unwrap();
+        assert_eq!(parsed, (5, 13, 9, 2024));
+        let (parsed, _) = Date.parse(&"sunday, 1 december 2024".into()).unwrap();
+        assert_eq!(parsed, (7, 1, 12, 2024));
+    }
+}
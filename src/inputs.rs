@@ -1,39 +1,269 @@
+use std::{any::Any, fmt::Debug, sync::Arc};
+
+use crate::{
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    parsers::{cst_p::Recording, span_p::Spanned},
+};
+
 #[derive(Clone, Debug)]
 pub struct Input {
     pub line: usize,
     pub col: usize,
+    /// How many bytes have been parsed before this input
+    pub offset: usize,
     pub source: String,
+    /// The indentation of the enclosing block, see `parsers::indent_p`
+    pub(crate) indent: usize,
+    /// More source may follow the end of this input, see `Input::partial`
+    partial: bool,
+    state: UserState,
+    /// The events of the lossless tree that is being built, see `parsers::cst_p`
+    pub(crate) cst: Recording,
+}
+
+/// Some user defined value that is carried along with the input.
+///
+/// The state is never mutated in place. Setting it makes a new input, so when a parser
+/// backtracks to an older input, any changes made to the state are undone. The state is
+/// shared between threads like the rest of the input, so it must be `Send` and `Sync`.
+#[derive(Clone, Default)]
+struct UserState(Option<Arc<dyn Any + Send + Sync>>);
+
+impl Debug for UserState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "UserState(..)"),
+            None => write!(f, "UserState(None)"),
+        }
+    }
+}
+
+/// A position in the source, as a line and column (counted in characters), and as a byte
+/// offset from the start of the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Position {
+    pub line: usize,
+    pub col: usize,
+    pub offset: usize,
+}
+
+/// The part of the source between two positions, `end` being exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /// The length of the span, in bytes
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The smallest span that contains both spans
+    pub fn join(self, other: Span) -> Span {
+        Span {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+/// Something that parsers can consume, such as an `Input` string or a stream of tokens
+pub trait ParserInput: Clone + Debug {
+    /// The position in the source of the next thing to be parsed, which is where errors
+    /// are reported
+    fn position(&self) -> Position;
+
+    /// Everything has been consumed
+    fn is_at_end(&self) -> bool;
+
+    /// How much is left to be parsed, such as bytes or tokens. It gets smaller whenever
+    /// something is consumed, even when the position does not move, as with tokens that
+    /// were made from no text.
+    fn remaining(&self) -> usize;
+
+    /// The source text between this input and `rest`, which was parsed from it, if the
+    /// input has source text
+    fn consumed(&self, rest: &Self) -> Option<String> {
+        let _ = rest;
+        None
+    }
+}
+
+impl ParserInput for Input {
+    fn position(&self) -> Position {
+        Input::position(self)
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.source.is_empty()
+    }
+
+    fn remaining(&self) -> usize {
+        self.source.len()
+    }
+
+    fn consumed(&self, rest: &Self) -> Option<String> {
+        self.source
+            .get(..rest.offset.checked_sub(self.offset)?)
+            .map(str::to_string)
+    }
+}
+
+/// A slice of tokens, each with the span of the source it was made from, that can be
+/// parsed much like an `Input` string. See `parsers::token_p`
+#[derive(Debug)]
+pub struct TokenInput<'a, T> {
+    pub tokens: &'a [Spanned<T>],
+    /// The position of the end of the source, used for errors once all of the tokens have
+    /// been parsed
+    pub end: Position,
+}
+
+impl<T> Clone for TokenInput<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TokenInput<'_, T> {}
+
+impl<'a, T> TokenInput<'a, T> {
+    pub fn new(tokens: &'a [Spanned<T>], end: Position) -> Self {
+        TokenInput { tokens, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Skip the first `count` tokens
+    pub fn token_offset(mut self, count: usize) -> Self {
+        self.tokens = &self.tokens[count.min(self.tokens.len())..];
+        self
+    }
+}
+
+impl<T> ParserInput for TokenInput<'_, T>
+where
+    T: Debug,
+{
+    fn position(&self) -> Position {
+        self.tokens
+            .first()
+            .map_or(self.end, |token| token.span.start)
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn remaining(&self) -> usize {
+        self.tokens.len()
+    }
 }
 
 impl Input {
     pub fn new(line: usize, col: usize, source: String) -> Self {
-        Input { line, col, source }
+        Input {
+            line,
+            col,
+            offset: 0,
+            source,
+            indent: 0,
+            partial: false,
+            state: UserState::default(),
+            cst: Recording::default(),
+        }
+    }
+
+    /// The indentation of the block that is currently being parsed
+    pub fn indent(&self) -> usize {
+        self.indent
+    }
+
+    /// Mark the input as only a part of the source, with more source to follow, such as
+    /// a buffer that is being filled from a file or a socket.
+    ///
+    /// Reaching the end of a partial input does not mean reaching the end of the source,
+    /// so a parser that needs to see what follows returns an `Incomplete` error instead of
+    /// guessing, and it should be run again once more source has been added.
+    pub fn partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// An `Incomplete` error, at the position of this input
+    pub fn incomplete(&self, needed: Needed) -> ParsingError {
+        ParsingError::new(ParsingErrorKind::Incomplete(needed), self.line, self.col)
     }
 
+    /// Attach some user defined state to the input, replacing any previous state
+    pub fn with_state<S>(mut self, state: S) -> Self
+    where
+        S: Any + Send + Sync,
+    {
+        self.state = UserState(Some(Arc::new(state)));
+        self
+    }
+
+    /// Get the state attached to the input, if there is one and it has type `S`
+    pub fn state<S>(&self) -> Option<&S>
+    where
+        S: Any,
+    {
+        self.state.0.as_ref()?.downcast_ref()
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            col: self.col,
+            offset: self.offset,
+        }
+    }
+
+    /// Skip the first `count` characters of the source, moving to the next line for
+    /// every new line that is skipped.
     pub fn char_offset(mut self, count: usize) -> Self {
-        self.col += count;
-        self.source = self.source.chars().skip(count).collect();
+        let mut skipped = 0;
+        for c in self.source.chars().take(count) {
+            skipped += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.col = 0;
+            } else {
+                self.col += 1;
+            }
+        }
+        self.offset += skipped;
+        self.source.drain(..skipped);
         self
     }
 }
 
-impl Into<Input> for String {
-    fn into(self) -> Input {
-        Input {
-            line: 0,
-            col: 0,
-            source: self,
-        }
+impl From<String> for Input {
+    fn from(source: String) -> Self {
+        Input::new(0, 0, source)
     }
 }
 
-impl Into<Input> for &str {
-    fn into(self) -> Input {
-        Input {
-            line: 0,
-            col: 0,
-            source: self.to_string(),
-        }
+impl From<&str> for Input {
+    fn from(source: &str) -> Self {
+        Input::new(0, 0, source.to_string())
     }
 }
 
@@ -47,6 +277,36 @@ mod test_input {
         let input = input.char_offset(4);
         assert_eq!(input.line, 3);
         assert_eq!(input.col, 16);
+        assert_eq!(input.offset, 4);
         assert_eq!(input.source, "o there!".to_string());
     }
+
+    #[test]
+    fn test_offset_over_lines() {
+        let input = Input::new(0, 5, "ab\ncd\n\u{e9}f".to_string());
+        let input = input.char_offset(4);
+        assert_eq!((input.line, input.col), (1, 1));
+        assert_eq!(input.source, "d\n\u{e9}f".to_string());
+
+        let input = input.char_offset(3);
+        assert_eq!((input.line, input.col), (2, 1));
+        assert_eq!(input.source, "f".to_string());
+
+        let input = input.char_offset(10);
+        assert_eq!((input.line, input.col), (2, 2));
+        assert_eq!(input.offset, 9);
+        assert!(input.source.is_empty());
+    }
+
+    #[test]
+    fn inputs_are_send_and_sync() {
+        fn check<T: Send + Sync>(_: &T) {}
+
+        let input = Input::from("ab").with_state(1u32);
+        check(&input);
+        let rest = std::thread::spawn(move || input.char_offset(1))
+            .join()
+            .unwrap();
+        assert_eq!(rest.state::<u32>(), Some(&1));
+    }
 }
@@ -1,8 +1,16 @@
 #![allow(dead_code)]
 
+#[cfg(feature = "async")]
+pub mod async_stream;
+pub mod derive;
+pub mod describe;
 pub mod errors;
+pub mod formats;
+pub mod grammar;
 pub mod inputs;
 pub mod parsers;
+pub mod peg;
+pub mod stream;
 pub mod traits;
 pub mod type_alias;
 
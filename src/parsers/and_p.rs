@@ -1,35 +1,23 @@
 use std::fmt::Debug;
 
-use crate::{inputs::Input, traits::Parser, type_alias::ParserRes};
-
-use super::{ParseMatch, ParseWhileOrNothing};
+use crate::{
+    describe::Description, errors::ParsingError, inputs::ParserInput, traits::Parser,
+    type_alias::ParserRes,
+};
 
 /// A parser that consists of first running some parser A, then some parser B.
 ///
 /// If either of the parsers give an error, that error will be returned. If they
 /// both suceed, their outputs will be combined using the `AndCombinator`, and
 /// that will be reuturned, with the rest of the unparsed string.
-pub struct AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
+pub struct AndThenParser<A, B, C> {
     pub first_parse: A,
     pub second_parse: B,
     combinator: C,
 }
 
-impl<A, B, C> AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
-    pub fn combine<NC>(self, combinator: NC) -> AndThenParser<A, B, NC>
-    where
-        NC: AndCombinator<A::Output, B::Output>,
-    {
+impl<A, B, C> AndThenParser<A, B, C> {
+    pub fn combine<NC>(self, combinator: NC) -> AndThenParser<A, B, NC> {
         AndThenParser {
             first_parse: self.first_parse,
             second_parse: self.second_parse,
@@ -81,17 +69,10 @@ pub struct KeepNone;
 
 impl<A, B> AndCombinator<A, B> for KeepNone {
     type Combined = ();
-    fn combine(&self, _: (A, B)) -> Self::Combined {
-        ()
-    }
+    fn combine(&self, _: (A, B)) -> Self::Combined {}
 }
 
-impl<A, B, C> From<(A, B, C)> for AndThenParser<A, B, C>
-where
-    A: Parser,
-    B: Parser,
-    C: AndCombinator<A::Output, B::Output>,
-{
+impl<A, B, C> From<(A, B, C)> for AndThenParser<A, B, C> {
     fn from((first_parse, second_parse, combinator): (A, B, C)) -> Self {
         Self {
             first_parse,
@@ -101,11 +82,7 @@ where
     }
 }
 
-impl<A, B> From<(A, B)> for AndThenParser<A, B, IdentityAndCombinator>
-where
-    A: Parser,
-    B: Parser,
-{
+impl<A, B> From<(A, B)> for AndThenParser<A, B, IdentityAndCombinator> {
     fn from((first_parse, second_parse): (A, B)) -> Self {
         Self {
             first_parse,
@@ -115,17 +92,22 @@ where
     }
 }
 
-impl<A, B, C> Parser for AndThenParser<A, B, C>
+impl<I, A, B, C> Parser<I> for AndThenParser<A, B, C>
 where
-    A: Parser,
-    B: Parser,
+    I: ParserInput,
+    A: Parser<I>,
+    B: Parser<I>,
     C: AndCombinator<A::Output, B::Output>,
     C::Combined: Debug,
 {
     type Output = C::Combined;
-    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
         let (a, rest) = A::parse(&self.first_parse, input)?;
         let (b, rest) = B::parse(&self.second_parse, &rest)?;
         Ok((C::combine(&self.combinator, (a, b)), rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::sequence(self.first_parse.describe(), self.second_parse.describe())
+    }
 }
@@ -0,0 +1,276 @@
+use std::ops::RangeInclusive;
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Parse a single character if it satisfies some predicate.
+///
+/// Unlike `ParseIf`, the predicate can be any closure, and the parser carries a label
+/// describing what it expected. When the predicate is not met, an `Expected` error with
+/// that label is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::char_p::satisfy;
+/// use parlib::traits::Parser;
+///
+/// let vowels = "aeiou";
+/// let vowel = satisfy(|c| vowels.contains(c)).label("a vowel");
+/// let (parsed, _) = vowel.parse(&"apple".into()).unwrap();
+/// assert_eq!(parsed, 'a');
+/// assert!(vowel.parse(&"pear".into()).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Satisfy<F>
+where
+    F: Fn(char) -> bool,
+{
+    predicate: F,
+    label: String,
+}
+
+impl<F> Satisfy<F>
+where
+    F: Fn(char) -> bool,
+{
+    pub fn new(predicate: F) -> Self {
+        Satisfy {
+            predicate,
+            label: "a character that satisfies a predicate".to_string(),
+        }
+    }
+
+    /// Describe the characters that this parser accepts, for use in errors
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+}
+
+impl<F> Parser for Satisfy<F>
+where
+    F: Fn(char) -> bool,
+{
+    type Output = char;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        match input.source.chars().next() {
+            Some(c) if (self.predicate)(c) => Ok((c, input.clone().char_offset(1))),
+            None if input.is_partial() => Err(input.incomplete(Needed::Size(1))),
+            _ => {
+                let kind = ParsingErrorKind::Expected(self.label.clone());
+                Err(ParsingError::new(kind, input.line, input.col))
+            }
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class(self.label.clone())
+    }
+}
+
+/// Parse a character that satisfies some predicate, see `Satisfy`
+pub fn satisfy<F>(predicate: F) -> Satisfy<F>
+where
+    F: Fn(char) -> bool,
+{
+    Satisfy::new(predicate)
+}
+
+/// Parse exactly the character `c`
+pub fn char(c: char) -> Satisfy<impl Fn(char) -> bool> {
+    satisfy(move |x| x == c).label(format!("{c:?}"))
+}
+
+/// Parse any of the characters in `chars`
+pub fn one_of(chars: &str) -> Satisfy<impl Fn(char) -> bool> {
+    let label = format!("one of {chars:?}");
+    let chars = chars.to_string();
+    satisfy(move |c| chars.contains(c)).label(label)
+}
+
+/// Parse any character that is not in `chars`
+pub fn none_of(chars: &str) -> Satisfy<impl Fn(char) -> bool> {
+    let label = format!("none of {chars:?}");
+    let chars = chars.to_string();
+    satisfy(move |c| !chars.contains(c)).label(label)
+}
+
+/// Parse any character within a range, such as `'a'..='z'`
+pub fn char_range(range: RangeInclusive<char>) -> Satisfy<impl Fn(char) -> bool> {
+    let label = format!("a character from {:?} to {:?}", range.start(), range.end());
+    satisfy(move |c| range.contains(&c)).label(label)
+}
+
+/// Parse an alphabetic character, as defined by `char::is_alphabetic`
+pub fn alpha() -> Satisfy<impl Fn(char) -> bool> {
+    satisfy(char::is_alphabetic).label("an alphabetic character")
+}
+
+/// Parse an ascii digit in some radix (from 2 to 36), so `digit(16)` accepts `0-9a-fA-F`.
+///
+/// Unlike `char::is_numeric`, other unicode digits, such as '٣', are not accepted.
+///
+/// # Panics
+///
+/// Panics if `radix` is smaller than 2 or larger than 36, rather than when the parser
+/// is first run.
+pub fn digit(radix: u32) -> Satisfy<impl Fn(char) -> bool> {
+    assert!(
+        (2..=36).contains(&radix),
+        "digit: radix must be from 2 to 36, got {radix}"
+    );
+    let label = match radix {
+        10 => "a digit".to_string(),
+        _ => format!("a base {radix} digit"),
+    };
+    satisfy(move |c| c.is_digit(radix)).label(label)
+}
+
+/// Parse a hex digit, `0-9a-fA-F`
+pub fn hex_digit() -> Satisfy<impl Fn(char) -> bool> {
+    satisfy(|c| c.is_ascii_hexdigit()).label("a hex digit")
+}
+
+/// Parse a character that can start an identifier (`XID_Start` or `_`), as described
+/// by Unicode Standard Annex #31. Without the `unicode-ident` feature, any alphabetic
+/// character or `_`.
+pub fn ident_start() -> Satisfy<impl Fn(char) -> bool> {
+    satisfy(|c| c == '_' || is_ident_start(c)).label("the start of an identifier")
+}
+
+/// Parse a character that can continue an identifier (`XID_Continue`), as described by
+/// Unicode Standard Annex #31. Without the `unicode-ident` feature, any alphanumeric
+/// character or `_`.
+pub fn ident_continue() -> Satisfy<impl Fn(char) -> bool> {
+    satisfy(is_ident_continue).label("an identifier character")
+}
+
+#[cfg(feature = "unicode-ident")]
+fn is_ident_start(c: char) -> bool {
+    unicode_ident::is_xid_start(c)
+}
+
+#[cfg(not(feature = "unicode-ident"))]
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic()
+}
+
+#[cfg(feature = "unicode-ident")]
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    unicode_ident::is_xid_continue(c)
+}
+
+#[cfg(not(feature = "unicode-ident"))]
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+#[cfg(test)]
+mod char_parser_test {
+    use super::*;
+
+    fn expected(err: ParsingError) -> String {
+        match err.kind() {
+            ParsingErrorKind::Expected(label) => label.clone(),
+            other => panic!("expected an Expected error, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn satisfy_with_closure() {
+        let limit = 'm';
+        let early = satisfy(move |c| c < limit);
+        let (p, rest) = early.parse(&"abc".into()).unwrap();
+        assert_eq!(p, 'a');
+        assert_eq!(rest.source, "bc");
+        assert_eq!(rest.col, 1);
+
+        let err = early
+            .label("an early letter")
+            .parse(&"xyz".into())
+            .unwrap_err();
+        assert_eq!(expected(err), "an early letter");
+        assert!(satisfy(|_| true).parse(&"".into()).is_err());
+    }
+
+    #[test]
+    fn single_and_sets_of_chars() {
+        assert_eq!(char('a').parse(&"ab".into()).unwrap().0, 'a');
+        assert_eq!(expected(char('a').parse(&"b".into()).unwrap_err()), "'a'");
+
+        let ops = one_of("+-*/");
+        assert_eq!(ops.parse(&"*2".into()).unwrap().0, '*');
+        assert_eq!(
+            expected(ops.parse(&"2".into()).unwrap_err()),
+            "one of \"+-*/\""
+        );
+
+        let not_quote = none_of("\"\\");
+        assert_eq!(not_quote.parse(&"a".into()).unwrap().0, 'a');
+        assert!(not_quote.parse(&"\"".into()).is_err());
+        assert!(not_quote.parse(&"".into()).is_err());
+    }
+
+    #[test]
+    fn ranges() {
+        let lower = char_range('a'..='z');
+        assert_eq!(lower.parse(&"q".into()).unwrap().0, 'q');
+        assert_eq!(
+            expected(lower.parse(&"Q".into()).unwrap_err()),
+            "a character from 'a' to 'z'"
+        );
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(digit(10).parse(&"7".into()).unwrap().0, '7');
+        assert!(digit(10).parse(&"a".into()).is_err());
+        assert!(digit(10).parse(&"\u{663}".into()).is_err());
+        assert!(digit(2).parse(&"2".into()).is_err());
+        assert_eq!(digit(16).parse(&"F".into()).unwrap().0, 'F');
+        assert_eq!(
+            expected(digit(8).parse(&"9".into()).unwrap_err()),
+            "a base 8 digit"
+        );
+        assert_eq!(hex_digit().parse(&"b".into()).unwrap().0, 'b');
+        assert!(hex_digit().parse(&"g".into()).is_err());
+        assert_eq!(alpha().parse(&"\u{e9}".into()).unwrap().0, '\u{e9}');
+        assert!(alpha().parse(&"1".into()).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be from 2 to 36")]
+    fn digit_radix_too_large() {
+        digit(37);
+    }
+
+    #[test]
+    fn identifiers() {
+        for c in ['a', 'Z', '_', '\u{3b1}', '\u{4e2d}'] {
+            assert!(ident_start().parse(&c.to_string().into()).is_ok(), "{c}");
+        }
+        for c in ['1', '-', ' ', '\u{663}'] {
+            assert!(ident_start().parse(&c.to_string().into()).is_err(), "{c}");
+        }
+        for c in ['a', '1', '_', '\u{663}'] {
+            assert!(ident_continue().parse(&c.to_string().into()).is_ok(), "{c}");
+        }
+        assert!(ident_continue().parse(&"-".into()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-ident")]
+    fn xid_identifiers() {
+        // A vertical tilde is a letter, but not one that can start an identifier
+        assert!(ident_start().parse(&"\u{2e2f}".into()).is_err());
+        // And a middle dot can continue an identifier, without being alphanumeric
+        assert!(ident_continue().parse(&"\u{b7}".into()).is_ok());
+        assert!(ident_start().parse(&"\u{b7}".into()).is_err());
+    }
+}
@@ -0,0 +1,536 @@
+use std::{fmt::Display, sync::Arc};
+
+use crate::{
+    describe::Description, inputs::Input, parsers::span_p::consumed_source, traits::Parser,
+    type_alias::ParserRes,
+};
+
+// While a lossless tree is being built, parsers record events in the input, rather than
+// building the tree as they go. The events are a persistent list, so when a parser
+// backtracks to an older input, the events that were recorded since are dropped with it.
+
+#[derive(Debug, Clone)]
+enum Event {
+    Start {
+        kind: String,
+        at: usize,
+    },
+    Token {
+        kind: String,
+        start: usize,
+        end: usize,
+    },
+    Finish {
+        at: usize,
+    },
+}
+
+#[derive(Debug)]
+struct Link {
+    event: Event,
+    previous: Option<Arc<Link>>,
+}
+
+/// The events that have been recorded for a lossless tree, see `Lossless`
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Recording {
+    recording: bool,
+    last: Option<Arc<Link>>,
+}
+
+impl Recording {
+    pub(crate) fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    fn push(&mut self, event: Event) {
+        self.last = Some(Arc::new(Link {
+            event,
+            previous: self.last.take(),
+        }));
+    }
+
+    /// Record a token, with the byte offsets of its start and end
+    pub(crate) fn token(&mut self, kind: &str, start: usize, end: usize) {
+        if self.recording && end > start {
+            let kind = kind.to_string();
+            self.push(Event::Token { kind, start, end });
+        }
+    }
+
+    fn events(&self) -> Vec<Event> {
+        let mut events = vec![];
+        let mut link = self.last.as_deref();
+        while let Some(l) = link {
+            events.push(l.event.clone());
+            link = l.previous.as_deref();
+        }
+        events.reverse();
+        events
+    }
+}
+
+/// The kind of the tokens that hold any source that was consumed outside of a token
+pub const TEXT: &str = "text";
+
+/// A leaf of a lossless tree: some kind of token, and its text
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenToken {
+    kind: String,
+    text: String,
+}
+
+impl GreenToken {
+    pub fn new(kind: &str, text: &str) -> Self {
+        GreenToken {
+            kind: kind.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> &str {
+        match self {
+            GreenElement::Node(node) => node.kind(),
+            GreenElement::Token(token) => token.kind(),
+        }
+    }
+
+    /// The length of the text of the element, in bytes
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len(),
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+/// A node of a lossless syntax tree, in the style of rowan's green trees.
+///
+/// Every character of the source belongs to exactly one token, so the text of the tree is
+/// the source that it was parsed from, including any whitespace and comments. Nodes do
+/// not know where they are in the source, only how long they are, so the same subtree can
+/// be shared by several trees.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GreenNode {
+    kind: String,
+    text_len: usize,
+    children: Vec<GreenElement>,
+}
+
+impl GreenNode {
+    pub fn new(kind: &str, children: Vec<GreenElement>) -> Self {
+        GreenNode {
+            kind: kind.to_string(),
+            text_len: children.iter().map(GreenElement::text_len).sum(),
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// The length of the text of the node, in bytes
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// The tokens of the node, in the order that they appear in the source
+    pub fn tokens(&self) -> Vec<Arc<GreenToken>> {
+        let mut tokens = vec![];
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => tokens.extend(node.tokens()),
+                GreenElement::Token(token) => tokens.push(token.clone()),
+            }
+        }
+        tokens
+    }
+
+    /// The text of the node, which is the source that it was parsed from
+    pub fn text(&self) -> String {
+        self.to_string()
+    }
+
+    /// Build a tree out of the events that were recorded while parsing `source`, which
+    /// starts at the byte offset `base`
+    fn build(kind: &str, source: &str, base: usize, events: Vec<Event>) -> Self {
+        let mut tree = TreeBuilder {
+            source,
+            base,
+            at: base,
+            stack: vec![(kind.to_string(), vec![])],
+        };
+        for event in events {
+            match event {
+                Event::Start { kind, at } => {
+                    tree.gap(at);
+                    tree.stack.push((kind, vec![]));
+                }
+                Event::Token { kind, start, end } => {
+                    tree.gap(start);
+                    tree.token(&kind, end);
+                }
+                Event::Finish { at } => {
+                    tree.gap(at);
+                    let (kind, children) = tree.stack.pop().expect("every finish has a start");
+                    let node = GreenNode::new(&kind, children);
+                    tree.push(GreenElement::Node(Arc::new(node)));
+                }
+            }
+        }
+        tree.gap(base + source.len());
+
+        let (kind, children) = tree.stack.pop().expect("the root is never finished");
+        GreenNode::new(&kind, children)
+    }
+}
+
+struct TreeBuilder<'s> {
+    source: &'s str,
+    /// The byte offset of the start of `source`
+    base: usize,
+    /// The byte offset of the end of the last token
+    at: usize,
+    /// The nodes that have been started, but not finished, with their children so far
+    stack: Vec<(String, Vec<GreenElement>)>,
+}
+
+impl TreeBuilder<'_> {
+    fn push(&mut self, element: GreenElement) {
+        let (_, children) = self.stack.last_mut().expect("the root is never finished");
+        children.push(element);
+    }
+
+    fn token(&mut self, kind: &str, end: usize) {
+        let text = &self.source[self.at - self.base..end - self.base];
+        self.push(GreenElement::Token(Arc::new(GreenToken::new(kind, text))));
+        self.at = end;
+    }
+
+    /// Anything between two events is source that no token claimed
+    fn gap(&mut self, to: usize) {
+        if to > self.at {
+            self.token(TEXT, to);
+        }
+    }
+}
+
+impl Display for GreenNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => write!(f, "{node}")?,
+                GreenElement::Token(token) => write!(f, "{}", token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Run some parser, and build a lossless tree of everything that it consumed, see
+/// `GreenNode`. This can be added to any parser with `Parser::lossless`.
+///
+/// Inside of it, parsers can be marked as nodes of the tree, with `Parser::node`, or as
+/// tokens, with `Parser::token`. Whitespace and comments that are skipped by a
+/// `TokenParser` become `whitespace` and `comment` tokens, and any other source that is
+/// not inside of a token becomes a `TEXT` token, so nothing is lost.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::{cst_p::GreenElement, lexeme_p::TokenParser, number_p::integer, ParseMatch};
+/// use parlib::traits::Parser;
+///
+/// let ws = TokenParser::new().line_comment("//").whitespace();
+/// let number = || integer::<u32>().token("number");
+/// let sum = number()
+///     .and_then(ws.clone())
+///     .and_then(ParseMatch("+").token("plus"))
+///     .and_then(ws)
+///     .and_then(number())
+///     .node("sum")
+///     .lossless("root");
+///
+/// let source = "1 // one\n+  2";
+/// let ((_, tree), _) = sum.parse(&source.into()).unwrap();
+/// assert_eq!(tree.text(), source);
+///
+/// let GreenElement::Node(sum) = &tree.children()[0] else { panic!() };
+/// let kinds = sum.children().iter().map(|c| c.kind()).collect::<Vec<_>>();
+/// assert_eq!(
+///     kinds,
+///     vec!["number", "whitespace", "comment", "whitespace", "plus", "whitespace", "number"]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lossless<P> {
+    parser: P,
+    kind: String,
+}
+
+impl<P> Lossless<P> {
+    pub fn new(parser: P, kind: &str) -> Self {
+        Lossless {
+            parser,
+            kind: kind.to_string(),
+        }
+    }
+}
+
+impl<P> Parser for Lossless<P>
+where
+    P: Parser,
+{
+    type Output = (P::Output, GreenNode);
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let mut start = input.clone();
+        start.cst = Recording {
+            recording: true,
+            last: None,
+        };
+
+        let (parsed, mut rest) = self.parser.parse(&start)?;
+        let consumed = consumed_source(input, &rest)?;
+        let tree = GreenNode::build(&self.kind, &consumed, input.offset, rest.cst.events());
+
+        // Whatever was being recorded before carries on, with the source of this tree as
+        // text that no token claimed
+        rest.cst = input.cst.clone();
+        Ok(((parsed, tree), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+/// Make a node of a lossless tree out of whatever some parser consumes, see `Lossless`
+#[derive(Debug, Clone)]
+pub struct CstNode<P> {
+    parser: P,
+    kind: String,
+}
+
+impl<P> CstNode<P> {
+    pub fn new(parser: P, kind: &str) -> Self {
+        CstNode {
+            parser,
+            kind: kind.to_string(),
+        }
+    }
+}
+
+impl<P> Parser for CstNode<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        if !input.cst.is_recording() {
+            return self.parser.parse(input);
+        }
+
+        let mut start = input.clone();
+        start.cst.push(Event::Start {
+            kind: self.kind.clone(),
+            at: input.offset,
+        });
+        let (parsed, mut rest) = self.parser.parse(&start)?;
+        let at = rest.offset;
+        rest.cst.push(Event::Finish { at });
+        Ok((parsed, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+/// Make a token of a lossless tree out of whatever some parser consumes, see `Lossless`.
+/// Tokens are leaves, so anything recorded by the parser itself is left out.
+#[derive(Debug, Clone)]
+pub struct CstToken<P> {
+    parser: P,
+    kind: String,
+}
+
+impl<P> CstToken<P> {
+    pub fn new(parser: P, kind: &str) -> Self {
+        CstToken {
+            parser,
+            kind: kind.to_string(),
+        }
+    }
+}
+
+impl<P> Parser for CstToken<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (parsed, mut rest) = self.parser.parse(input)?;
+        if rest.cst.is_recording() {
+            rest.cst = input.cst.clone();
+            rest.cst.token(&self.kind, input.offset, rest.offset);
+        }
+        Ok((parsed, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_cst {
+    use super::{GreenElement, GreenNode, GreenToken, TEXT};
+    use crate::{
+        inputs::Input,
+        parsers::{
+            lexeme_p::TokenParser, number_p::integer, repeat_p::RepeatParser, ParseMatch,
+            ParseWhile,
+        },
+        traits::Parser,
+        type_alias::ParserRes,
+    };
+
+    /// A broken parser, which goes back to the start of the source
+    struct Rewind;
+
+    impl Parser for Rewind {
+        type Output = ();
+        fn parse(&self, _: &Input) -> ParserRes<Self::Output> {
+            Ok(((), Input::from("abc")))
+        }
+    }
+
+    fn kinds(node: &GreenNode) -> Vec<&str> {
+        node.children().iter().map(GreenElement::kind).collect()
+    }
+
+    #[test]
+    fn round_trips() {
+        let tokens = TokenParser::new()
+            .line_comment("#")
+            .block_comment("/*", "*/");
+        let ident = tokens.lexeme(ParseWhile(|c| c.is_alphabetic()).token("ident"));
+        let list = tokens
+            .whitespace()
+            .and_then(tokens.lexeme(ParseMatch("(").token("open")))
+            .and_then(RepeatParser::new(ident.node("item")).minm(0))
+            .and_then(ParseMatch(")"))
+            .lossless("list");
+
+        let source = " ( a /* b */ c # d\n\te)";
+        let ((_, tree), rest) = list.parse(&source.into()).unwrap();
+        assert!(rest.source.is_empty());
+        assert_eq!(tree.text(), source);
+        assert_eq!(tree.text_len(), source.len());
+        assert_eq!(
+            kinds(&tree),
+            vec![
+                "whitespace",
+                "open",
+                "whitespace",
+                "item",
+                "item",
+                "item",
+                TEXT
+            ]
+        );
+
+        let GreenElement::Node(item) = &tree.children()[4] else {
+            panic!("expected a node");
+        };
+        assert_eq!(
+            kinds(item),
+            vec!["ident", "whitespace", "comment", "whitespace"]
+        );
+        assert_eq!(item.text(), "c # d\n\t");
+    }
+
+    #[test]
+    fn backtracking_drops_events() {
+        let number = integer::<u32>().token("number").node("number");
+        let word = ParseWhile(|c| c.is_alphabetic()).token("word");
+        // The first option records a node before it fails
+        let value = number
+            .and_then(ParseMatch("!"))
+            .node("exclaimed")
+            .otherwise(integer::<u32>().and_then(word).node("measure"));
+
+        let ((_, tree), _) = value.lossless("root").parse(&"12cm".into()).unwrap();
+        assert_eq!(kinds(&tree), vec!["measure"]);
+        assert_eq!(
+            tree.tokens()
+                .iter()
+                .map(|t| (t.kind(), t.text()))
+                .collect::<Vec<_>>(),
+            vec![(TEXT, "12"), ("word", "cm")]
+        );
+    }
+
+    #[test]
+    fn lossless_checks_the_rest() {
+        let input = Input::from("abc").char_offset(2);
+        let err = Rewind.lossless("root").parse(&input).unwrap_err();
+        assert_eq!(err.col(), 2);
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    fn only_records_inside_lossless() {
+        let number = integer::<u32>().token("number").node("number");
+        let (parsed, rest) = number.parse(&"7".into()).unwrap();
+        assert_eq!(parsed, 7);
+        assert!(!rest.cst.is_recording());
+
+        // Tokens are leaves
+        let pair = integer::<u32>()
+            .token("inner")
+            .and_then(ParseMatch(" "))
+            .and_then(integer::<u32>().token("inner"))
+            .token("outer");
+        let ((_, tree), _) = pair.lossless("root").parse(&"1 2".into()).unwrap();
+        assert_eq!(
+            tree,
+            GreenNode::new(
+                "root",
+                vec![GreenElement::Token(GreenToken::new("outer", "1 2").into())]
+            )
+        );
+    }
+
+    #[test]
+    fn trees_are_send_and_sync() {
+        fn check<T: Send + Sync>(_: &T) {}
+
+        let number = integer::<u32>().token("number").node("number");
+        let ((_, tree), _) = number.lossless("root").parse(&"42".into()).unwrap();
+        check(&tree);
+        let text = std::thread::spawn(move || tree.text()).join().unwrap();
+        assert_eq!(text, "42");
+    }
+}
@@ -0,0 +1,447 @@
+use crate::{
+    describe::Description,
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::Input,
+    parsers::span_p::consumed_source,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+// Indentation is measured in characters from the start of the line, and the input keeps
+// track of the indentation of the block that is being parsed (`Input::indent`), starting
+// at 0. Between lines, only whitespace is skipped.
+
+fn skip_whitespace(input: &Input) -> Input {
+    let len = input
+        .source
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .count();
+    input.clone().char_offset(len)
+}
+
+fn indentation_error(input: &Input, message: String) -> ParsingError {
+    let kind = ParsingErrorKind::IncorrectIndentation(message);
+    ParsingError::new(kind, input.line, input.col)
+}
+
+fn spaces(count: usize) -> String {
+    match count {
+        1 => "1 space".to_string(),
+        _ => format!("{count} spaces"),
+    }
+}
+
+/// Skip any whitespace, and then run some parser, as long as it starts at the indentation
+/// of the current block.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::indent_p::same_indent;
+/// use parlib::parsers::ParseMatch;
+/// use parlib::traits::Parser;
+///
+/// let (_, rest) = same_indent(ParseMatch("a")).parse(&"\n\na".into()).unwrap();
+/// assert!(rest.source.is_empty());
+///
+/// // The top level block has no indentation
+/// assert!(same_indent(ParseMatch("a")).parse(&"\n  a".into()).is_err());
+/// ```
+pub struct SameIndent<P>
+where
+    P: Parser,
+{
+    parser: P,
+}
+
+impl<P> Parser for SameIndent<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let start = skip_whitespace(input);
+        if start.col != start.indent {
+            let message = format!(
+                "expected indentation of {}, found {}",
+                spaces(start.indent),
+                start.col
+            );
+            return Err(indentation_error(&start, message));
+        }
+        self.parser.parse(&start)
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+pub fn same_indent<P>(parser: P) -> SameIndent<P>
+where
+    P: Parser,
+{
+    SameIndent { parser }
+}
+
+/// Parse a header, followed by a block of one or more items on the following lines.
+///
+/// The first item must be indented more than the current block, and all other items must
+/// have the same indentation as the first one. Items can contain blocks of their own.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::indent_p::indented_block;
+/// use parlib::parsers::{ParseMatch, ParseWhile};
+/// use parlib::traits::Parser;
+///
+/// let block = indented_block(ParseMatch("items:"), ParseWhile(|c| c.is_alphabetic()));
+/// let ((_, items), _) = block.parse(&"items:\n  a\n  b\nnext".into()).unwrap();
+/// assert_eq!(items, vec!["a".to_string(), "b".to_string()]);
+///
+/// // b is indented more than a
+/// let err = block.parse(&"items:\n  a\n    b".into()).unwrap_err();
+/// assert_eq!((err.line(), err.col()), (2, 4));
+/// ```
+pub struct IndentedBlock<H, P>
+where
+    H: Parser,
+    P: Parser,
+{
+    header: H,
+    item: P,
+}
+
+impl<H, P> Parser for IndentedBlock<H, P>
+where
+    H: Parser,
+    P: Parser,
+{
+    type Output = (H::Output, Vec<P::Output>);
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (header, rest) = self.header.parse(input)?;
+
+        let mut start = skip_whitespace(&rest);
+        if start.line == rest.line {
+            let message = "expected the block to start on a new line".to_string();
+            return Err(indentation_error(&start, message));
+        }
+        if start.col <= input.indent {
+            let message = format!(
+                "expected indentation of more than {}, found {}",
+                spaces(input.indent),
+                start.col
+            );
+            return Err(indentation_error(&start, message));
+        }
+
+        let block_indent = start.col;
+        start.indent = block_indent;
+
+        let mut items = vec![];
+        let mut rest = start;
+        loop {
+            let (item, after) = self.item.parse(&rest)?;
+            items.push(item);
+            rest = after;
+
+            let next = skip_whitespace(&rest);
+            if next.source.is_empty() || next.line == rest.line || next.col < block_indent {
+                break;
+            }
+            if next.col > block_indent {
+                let message = format!(
+                    "expected indentation of {}, found {}",
+                    spaces(block_indent),
+                    next.col
+                );
+                return Err(indentation_error(&next, message));
+            }
+            rest = next;
+        }
+
+        rest.indent = input.indent;
+        Ok(((header, items), rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::sequence(
+            self.header.describe(),
+            Description::repeat(self.item.describe(), 1, None),
+        )
+    }
+}
+
+pub fn indented_block<H, P>(header: H, item: P) -> IndentedBlock<H, P>
+where
+    H: Parser,
+    P: Parser,
+{
+    IndentedBlock { header, item }
+}
+
+/// Run some parser over a line, and any following lines that are indented more than the
+/// current block, as if they were all one line.
+///
+/// The parser cannot see past the end of the fold, so whitespace skipping parsers (such as
+/// `lexeme`) can be used freely inside of it.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::indent_p::line_fold;
+/// use parlib::parsers::lexeme_p::identifier;
+/// use parlib::parsers::repeat_p::RepeatParser;
+/// use parlib::traits::Parser;
+///
+/// let words = line_fold(RepeatParser::new(identifier().lexeme()));
+/// let (parsed, rest) = words.parse(&"one two\n  three\nfour".into()).unwrap();
+/// assert_eq!(parsed, vec!["one", "two", "three"]);
+/// assert_eq!(rest.source, "\nfour".to_string());
+/// ```
+pub struct LineFold<P>
+where
+    P: Parser,
+{
+    parser: P,
+}
+
+impl<P> LineFold<P>
+where
+    P: Parser,
+{
+    /// The length in bytes of the fold at the start of `source`, and whether a line that
+    /// is not part of it was found, which ends the fold
+    fn fold_len(&self, source: &str, indent: usize) -> (usize, bool) {
+        let mut len = source.find('\n').unwrap_or(source.len());
+        let mut line_start = len;
+        while line_start < source.len() {
+            // Skip the new line
+            line_start += 1;
+            let line_end = source[line_start..]
+                .find('\n')
+                .map_or(source.len(), |i| line_start + i);
+            let line = &source[line_start..line_end];
+            let content = line.trim_start();
+
+            if !content.is_empty() {
+                if line[..line.len() - content.len()].chars().count() <= indent {
+                    return (len, true);
+                }
+                len = line_end;
+            }
+            line_start = line_end;
+        }
+        (len, false)
+    }
+}
+
+impl<P> Parser for LineFold<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (len, ended) = self.fold_len(&input.source, input.indent);
+        let mut folded = input.clone();
+        folded.source.truncate(len);
+        // Only a fold that runs to the end of a partial input may go on in the next part
+        if ended {
+            folded = folded.partial(false);
+        }
+
+        let (parsed, mut rest) = self.parser.parse(&folded)?;
+        let consumed = consumed_source(input, &rest)?;
+        rest.source = input.source[consumed.len()..].to_string();
+        Ok((parsed, rest.partial(input.is_partial())))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+pub fn line_fold<P>(parser: P) -> LineFold<P>
+where
+    P: Parser,
+{
+    LineFold { parser }
+}
+
+#[cfg(test)]
+mod test_indent {
+    use super::{indented_block, line_fold, same_indent};
+    use crate::{
+        errors::{ParsingError, ParsingErrorKind},
+        inputs::Input,
+        parsers::{
+            and_p::KeepFirstOutputOnly, lexeme_p::identifier, repeat_p::RepeatParser, ParseMatch,
+        },
+        traits::Parser,
+        type_alias::ParserRes,
+    };
+
+    /// A broken parser, which goes back to the start of the source
+    struct Rewind;
+
+    impl Parser for Rewind {
+        type Output = ();
+        fn parse(&self, _: &Input) -> ParserRes<Self::Output> {
+            Ok(((), Input::from("abc")))
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Tree {
+        name: String,
+        children: Vec<Tree>,
+    }
+
+    struct TreeParser;
+
+    impl Parser for TreeParser {
+        type Output = Tree;
+        fn parse(&self, input: &crate::inputs::Input) -> crate::type_alias::ParserRes<Tree> {
+            let header = identifier()
+                .and_then(ParseMatch(":"))
+                .combine(KeepFirstOutputOnly);
+            let node = indented_block(header, TreeParser)
+                .with_mapping(&|(name, children)| Tree { name, children });
+            let leaf = identifier().with_mapping(&|name| Tree {
+                name,
+                children: vec![],
+            });
+            node.otherwise(leaf).parse(input)
+        }
+    }
+
+    fn leaf(name: &str) -> Tree {
+        Tree {
+            name: name.to_string(),
+            children: vec![],
+        }
+    }
+
+    fn indentation_message(err: ParsingError) -> (String, usize, usize) {
+        match err.kind() {
+            ParsingErrorKind::IncorrectIndentation(message) => {
+                (message.clone(), err.line(), err.col())
+            }
+            other => panic!("expected an indentation error, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn nested_blocks() {
+        let source = "root:\n  a\n  b:\n    c\n\n    d\n  e\nother";
+        let (trees, rest) = RepeatParser::new(same_indent(TreeParser))
+            .parse(&source.into())
+            .unwrap();
+        assert!(rest.source.is_empty());
+        assert_eq!(
+            trees,
+            vec![
+                Tree {
+                    name: "root".to_string(),
+                    children: vec![
+                        leaf("a"),
+                        Tree {
+                            name: "b".to_string(),
+                            children: vec![leaf("c"), leaf("d")],
+                        },
+                        leaf("e"),
+                    ],
+                },
+                leaf("other"),
+            ]
+        );
+        assert_eq!(rest.indent(), 0);
+    }
+
+    #[test]
+    fn indentation_errors() {
+        let block = indented_block(ParseMatch("root:"), identifier());
+        let (_, rest) = block.parse(&"root:\n    a\n  b".into()).unwrap();
+        let err = same_indent(identifier()).parse(&rest).unwrap_err();
+        assert_eq!(
+            indentation_message(err),
+            (
+                "expected indentation of 0 spaces, found 2".to_string(),
+                2,
+                2
+            )
+        );
+
+        let err = block.parse(&"root:\n    a\n      b".into()).unwrap_err();
+        assert_eq!(
+            indentation_message(err),
+            (
+                "expected indentation of 4 spaces, found 6".to_string(),
+                2,
+                6
+            )
+        );
+
+        let err = block.parse(&"root:\nchild".into()).unwrap_err();
+        assert_eq!(
+            indentation_message(err),
+            (
+                "expected indentation of more than 0 spaces, found 0".to_string(),
+                1,
+                0
+            )
+        );
+
+        let err = block.parse(&"root: child".into()).unwrap_err();
+        assert_eq!(
+            indentation_message(err).0,
+            "expected the block to start on a new line"
+        );
+    }
+
+    #[test]
+    fn folded_lines() {
+        let words = line_fold(RepeatParser::new(identifier().lexeme()));
+        let source = "key: one\n   two\n\n  three\nnext";
+        let (_, rest) = ParseMatch("key: ").parse(&source.into()).unwrap();
+        let (p, rest) = words.parse(&rest).unwrap();
+        assert_eq!(p, vec!["one", "two", "three"]);
+        assert_eq!(rest.source, "\nnext");
+        assert_eq!((rest.line, rest.col), (3, 7));
+
+        // A trailing blank line is not part of the fold
+        let (p, rest) = words.parse(&"a\n\nb".into()).unwrap();
+        assert_eq!(p, vec!["a"]);
+        assert_eq!(rest.source, "\n\nb");
+    }
+
+    #[test]
+    fn line_fold_checks_the_rest() {
+        let input = Input::from("abc").char_offset(2);
+        let err = line_fold(Rewind).parse(&input).unwrap_err();
+        assert_eq!(err.col(), 2);
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    fn folded_lines_partial_input() {
+        let words = line_fold(RepeatParser::new(identifier().lexeme()));
+        let partial = |source: &str| Input::from(source).partial(true);
+        let (p, rest) = words.parse(&partial("one two\nfour more")).unwrap();
+        assert_eq!(p, vec!["one", "two"]);
+        assert_eq!(rest.source, "\nfour more");
+        assert!(rest.is_partial());
+
+        // The next part of the input may indent another line of the fold
+        for source in ["one two", "one two\n", "one two\n  three\n\n"] {
+            assert!(
+                words
+                    .parse(&partial(source))
+                    .is_err_and(|e| e.is_incomplete()),
+                "{source:?}"
+            );
+        }
+    }
+}
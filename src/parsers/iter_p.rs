@@ -0,0 +1,218 @@
+use crate::{
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::ParserInput,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// An iterator that parses one record after another from some input, see `Parser::iter`.
+///
+/// Unlike `RepeatParser`, the records are not collected, and an error is returned as the
+/// last item rather than ending the iteration silently. The iterator ends cleanly once
+/// all of the input has been parsed.
+///
+/// Records can be separated by some separator, such as a new line. A separator after the
+/// last record is allowed.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::{number_p::integer, ParseMatch};
+/// use parlib::traits::Parser;
+///
+/// let numbers = integer::<u32>();
+/// let records = numbers
+///     .iter(&"1\n2\n3\n".into())
+///     .separated_by(ParseMatch("\n"))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(records, vec![1, 2, 3]);
+///
+/// let mut records = numbers.iter(&"1\nx".into()).separated_by(ParseMatch("\n"));
+/// assert_eq!(records.next().unwrap().unwrap(), 1);
+/// assert!(records.next().unwrap().is_err());
+/// assert!(records.next().is_none());
+/// ```
+pub struct ParseIter<'p, P, I, S = NoSeparator> {
+    parser: &'p P,
+    separator: S,
+    rest: I,
+    started: bool,
+    done: bool,
+}
+
+impl<'p, P, I> ParseIter<'p, P, I>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    pub fn new(parser: &'p P, input: I) -> Self {
+        ParseIter {
+            parser,
+            separator: NoSeparator,
+            rest: input,
+            started: false,
+            done: false,
+        }
+    }
+}
+
+impl<'p, P, I, S> ParseIter<'p, P, I, S>
+where
+    I: ParserInput,
+    P: Parser<I>,
+    S: Parser<I>,
+{
+    /// Parse a separator between every two records
+    pub fn separated_by<NS>(self, separator: NS) -> ParseIter<'p, P, I, NS>
+    where
+        NS: Parser<I>,
+    {
+        ParseIter {
+            parser: self.parser,
+            separator,
+            rest: self.rest,
+            started: self.started,
+            done: self.done,
+        }
+    }
+
+    /// The input that has not been parsed yet. After an error, this is the input that
+    /// the failing record was parsed from.
+    pub fn rest(&self) -> &I {
+        &self.rest
+    }
+
+    fn next_record(&mut self) -> Option<Result<P::Output, ParsingError>> {
+        let mut rest = self.rest.clone();
+        if self.started {
+            match self.separator.parse(&rest) {
+                Ok((_, r)) => rest = r,
+                Err(_) if rest.is_at_end() => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        if rest.is_at_end() {
+            return None;
+        }
+
+        let (parsed, r) = match self.parser.parse(&rest) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.rest = rest;
+                return Some(Err(e));
+            }
+        };
+
+        // A record that did not consume anything would be parsed forever
+        if r.remaining() == self.rest.remaining() {
+            let at = r.position();
+            let kind = ParsingErrorKind::PatternNotFound(
+                "the parser did not consume any input".to_string(),
+            );
+            return Some(Err(ParsingError::new(kind, at.line, at.col)));
+        }
+
+        self.started = true;
+        self.rest = r;
+        Some(Ok(parsed))
+    }
+}
+
+impl<P, I, S> Iterator for ParseIter<'_, P, I, S>
+where
+    I: ParserInput,
+    P: Parser<I>,
+    S: Parser<I>,
+{
+    type Item = Result<P::Output, ParsingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_record();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+/// A separator that parses nothing, used by `ParseIter` when there is no separator
+#[derive(Debug, Clone, Copy)]
+pub struct NoSeparator;
+
+impl<I> Parser<I> for NoSeparator
+where
+    I: ParserInput,
+{
+    type Output = ();
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        Ok(((), input.clone()))
+    }
+}
+
+#[cfg(test)]
+mod test_iter {
+    use crate::{
+        errors::ParsingErrorKind,
+        parsers::{
+            lexeme_p::TokenParser, repeat_p::repeat, ParseMatch, ParseWhile, ParseWhileOrNothing,
+        },
+        traits::Parser,
+    };
+
+    #[test]
+    fn records_until_end() {
+        let cell = ParseWhile(|c| c != ',' && c != '\n');
+        let row = cell.and_then(repeat(cell.preceed(ParseMatch(","))).minm(0));
+        let rows = row
+            .iter(&"a,b\nc\ne,f,g\n".into())
+            .separated_by(ParseMatch("\n"))
+            .map(|row| row.map(|(first, rest)| format!("{first}{}", rest.concat())))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(rows, vec!["ab", "c", "efg"]);
+    }
+
+    #[test]
+    fn without_separator() {
+        let word = ParseWhile(char::is_alphabetic).lexeme();
+        let mut words = word.iter(&"one two  three ".into());
+        assert_eq!(words.next().unwrap().unwrap(), "one");
+        assert_eq!(words.next().unwrap().unwrap(), "two");
+        assert_eq!(words.next().unwrap().unwrap(), "three");
+        assert!(words.next().is_none());
+        assert!(words.rest().source.is_empty());
+        assert!(word.iter(&"".into()).next().is_none());
+    }
+
+    #[test]
+    fn stops_at_first_error() {
+        let word = ParseWhile(char::is_alphabetic);
+        let mut words = word
+            .iter(&"ab;cd;12;ef".into())
+            .separated_by(ParseMatch(";"));
+        assert!(words.next().unwrap().is_ok());
+        assert!(words.next().unwrap().is_ok());
+        let err = words.next().unwrap().unwrap_err();
+        assert_eq!(err.col(), 6);
+        assert!(words.next().is_none());
+        assert_eq!(words.rest().source, "12;ef".to_string());
+
+        // A missing separator is an error, rather than the end of the records
+        let mut words = word.iter(&"ab cd".into()).separated_by(ParseMatch(";"));
+        assert!(words.next().unwrap().is_ok());
+        assert!(words.next().unwrap().is_err());
+
+        // Records that consume nothing are an error, rather than an endless iterator
+        let mut empty = ParseWhileOrNothing(char::is_alphabetic).iter(&"12".into());
+        assert!(matches!(
+            empty.next().unwrap().unwrap_err().kind(),
+            ParsingErrorKind::PatternNotFound(_)
+        ));
+        assert!(empty.next().is_none());
+
+        let trivia = TokenParser::new().whitespace();
+        assert!(trivia.iter(&"  ".into()).next().unwrap().is_ok());
+    }
+}
@@ -0,0 +1,505 @@
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+use super::{
+    char_p::{ident_start, is_ident_continue},
+    ParseMatch, ParseWhileOrNothing,
+};
+
+/// Parse a keyword, making sure that it is not just the start of a longer word.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::lexeme_p::keyword;
+/// use parlib::traits::Parser;
+///
+/// let (parsed, rest) = keyword("if").parse(&"if x".into()).unwrap();
+/// assert_eq!(parsed, "if".to_string());
+/// assert_eq!(rest.source, " x".to_string());
+///
+/// // `ifx` is an identifier, not the keyword `if`
+/// assert!(keyword("if").parse(&"ifx".into()).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keyword {
+    word: String,
+}
+
+impl Parser for Keyword {
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let error = || {
+            let kind = ParsingErrorKind::Expected(format!("keyword {:?}", self.word));
+            ParsingError::new(kind, input.line, input.col)
+        };
+
+        let (word, rest) = ParseMatch(self.word.as_str()).parse(input).map_err(|e| {
+            if e.is_incomplete() {
+                e
+            } else {
+                error()
+            }
+        })?;
+        // The next part of a partial input could continue the word
+        if rest.is_partial() && rest.source.is_empty() {
+            return Err(input.incomplete(Needed::Unknown));
+        }
+        if rest.source.starts_with(is_ident_continue) {
+            return Err(error());
+        }
+        Ok((word, rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Literal(self.word.clone())
+    }
+}
+
+pub fn keyword(word: &str) -> Keyword {
+    Keyword {
+        word: word.to_string(),
+    }
+}
+
+/// Parse an identifier, as described by Unicode Standard Annex #31 (see `ident_start`
+/// and `ident_continue`).
+///
+/// Reserved words can be added, and the parser will give a `ReservedWord` error if the
+/// identifier is one of them.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::lexeme_p::identifier;
+/// use parlib::traits::Parser;
+///
+/// let ident = identifier().reserved(&["if", "else"]);
+/// let (parsed, _) = ident.parse(&"iffy = 1".into()).unwrap();
+/// assert_eq!(parsed, "iffy".to_string());
+/// assert!(ident.parse(&"if x".into()).is_err());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Identifier {
+    reserved: Vec<String>,
+}
+
+impl Identifier {
+    /// Words that may not be used as identifiers
+    pub fn reserved(mut self, words: &[&str]) -> Self {
+        self.reserved
+            .extend(words.iter().map(|word| word.to_string()));
+        self
+    }
+}
+
+impl Parser for Identifier {
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (first, rest) = ident_start().parse(input)?;
+        let (tail, rest) = ParseWhileOrNothing(is_ident_continue).parse(&rest)?;
+        let ident = format!("{first}{tail}");
+
+        if self.reserved.contains(&ident) {
+            let kind = ParsingErrorKind::ReservedWord(ident);
+            return Err(ParsingError::new(kind, input.line, input.col));
+        }
+        Ok((ident, rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("identifier".to_string())
+    }
+}
+
+pub fn identifier() -> Identifier {
+    Identifier::default()
+}
+
+/// Describes the trivia of a language: the whitespace and comments that may appear
+/// between any two tokens, in the style of Parsec's `TokenParser`.
+///
+/// By default only whitespace is trivia. Line comments, and block comments (which may
+/// optionally nest) can be added.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::lexeme_p::{identifier, TokenParser};
+/// use parlib::parsers::number_p::integer;
+/// use parlib::traits::Parser;
+///
+/// let tokens = TokenParser::new()
+///     .line_comment("//")
+///     .block_comment("/*", "*/");
+///
+/// let assignment = tokens
+///     .lexeme(identifier())
+///     .and_then(tokens.symbol("="))
+///     .and_then(tokens.lexeme(integer::<i32>()));
+///
+/// let source = "x /* the answer */ = // to everything\n 42  ";
+/// let (((name, _), value), rest) = assignment.parse(&source.into()).unwrap();
+/// assert_eq!(name, "x".to_string());
+/// assert_eq!(value, 42);
+/// assert!(rest.source.is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TokenParser {
+    line_comment: Option<String>,
+    block_comment: Option<(String, String)>,
+    nested_comments: bool,
+}
+
+impl TokenParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A comment that starts with `start` and lasts until the end of the line
+    pub fn line_comment(mut self, start: &str) -> Self {
+        self.line_comment = Some(start.to_string());
+        self
+    }
+
+    /// A comment that starts with `start` and lasts until `end`
+    pub fn block_comment(mut self, start: &str, end: &str) -> Self {
+        self.block_comment = Some((start.to_string(), end.to_string()));
+        self
+    }
+
+    /// Allow block comments to contain other block comments
+    pub fn nested_comments(mut self, nested: bool) -> Self {
+        self.nested_comments = nested;
+        self
+    }
+
+    /// A parser that skips any amount of trivia
+    pub fn whitespace(&self) -> Whitespace {
+        Whitespace {
+            trivia: self.clone(),
+        }
+    }
+
+    /// Run some parser, and then skip any trivia that follows it
+    pub fn lexeme<P>(&self, parser: P) -> Lexeme<P>
+    where
+        P: Parser,
+    {
+        Lexeme {
+            parser,
+            trivia: self.clone(),
+        }
+    }
+
+    /// Parse an exact string, and then skip any trivia that follows it
+    pub fn symbol(&self, symbol: &str) -> Lexeme<ParseMatch<String>> {
+        self.lexeme(ParseMatch(symbol.to_string()))
+    }
+
+    /// Find how many bytes of trivia are at the start of `source`, calling `piece` with the
+    /// kind and the byte range of each piece of trivia. On error, the byte offset of the
+    /// unterminated block comment is returned.
+    ///
+    /// If `partial`, the trivia may not reach the end of the source, since the next part
+    /// of the source could carry on a comment, or finish the start of one.
+    fn skip(
+        &self,
+        source: &str,
+        partial: bool,
+        mut piece: impl FnMut(&str, usize, usize),
+    ) -> Result<usize, (usize, String)> {
+        let unfinished = |i| (i, "the trivia may go on in the next part".to_string());
+        let mut i = 0;
+        loop {
+            let rest = &source[i..];
+            let trimmed = rest.trim_start();
+            if trimmed.len() != rest.len() {
+                piece("whitespace", i, i + rest.len() - trimmed.len());
+                i += rest.len() - trimmed.len();
+                continue;
+            }
+
+            if partial && !rest.is_empty() {
+                let starts = [
+                    self.line_comment.as_deref(),
+                    self.block_comment.as_ref().map(|(start, _)| start.as_str()),
+                ];
+                if starts
+                    .into_iter()
+                    .flatten()
+                    .any(|start| start.len() > rest.len() && start.starts_with(rest))
+                {
+                    return Err(unfinished(i));
+                }
+            }
+
+            if let Some(start) = self.line_comment.as_deref() {
+                if rest.starts_with(start) {
+                    let Some(len) = rest.find('\n').or((!partial).then_some(rest.len())) else {
+                        return Err(unfinished(i));
+                    };
+                    piece("comment", i, i + len);
+                    i += len;
+                    continue;
+                }
+            }
+
+            if let Some((start, end)) = &self.block_comment {
+                if rest.starts_with(start.as_str()) {
+                    let len = self.skip_block_comment(rest, start, end).ok_or_else(|| {
+                        (
+                            i,
+                            format!("Did not find the end of the block comment {end}"),
+                        )
+                    })?;
+                    piece("comment", i, i + len);
+                    i += len;
+                    continue;
+                }
+            }
+
+            return Ok(i);
+        }
+    }
+
+    /// Find the length of the block comment at the start of `source`
+    fn skip_block_comment(&self, source: &str, start: &str, end: &str) -> Option<usize> {
+        let mut depth = 0;
+        let mut i = 0;
+        loop {
+            let rest = &source[i..];
+            if rest.starts_with(start) && (depth == 0 || self.nested_comments) {
+                depth += 1;
+                i += start.len();
+            } else if rest.starts_with(end) {
+                depth -= 1;
+                i += end.len();
+                if depth == 0 {
+                    return Some(i);
+                }
+            } else {
+                i += rest.chars().next()?.len_utf8();
+            }
+        }
+    }
+
+    fn skip_input(&self, input: &Input) -> ParserRes<()> {
+        // While building a lossless tree, the trivia is kept as tokens (see `cst_p`)
+        let mut cst = input.cst.clone();
+        let skipped = self.skip(&input.source, input.is_partial(), |kind, start, end| {
+            cst.token(kind, input.offset + start, input.offset + end)
+        });
+        match skipped {
+            Ok(len) => {
+                let mut rest = input
+                    .clone()
+                    .char_offset(input.source[..len].chars().count());
+                rest.cst = cst;
+                Ok(((), rest))
+            }
+            // The rest of the trivia may be in the next part of a partial input
+            Err(_) if input.is_partial() => Err(input.incomplete(Needed::Unknown)),
+            Err((at, message)) => {
+                let at = input
+                    .clone()
+                    .char_offset(input.source[..at].chars().count());
+                let kind = ParsingErrorKind::PatternNotFound(message);
+                Err(ParsingError::new(kind, at.line, at.col))
+            }
+        }
+    }
+}
+
+/// Skip any amount of trivia, see `TokenParser`
+#[derive(Debug, Clone)]
+pub struct Whitespace {
+    trivia: TokenParser,
+}
+
+impl Parser for Whitespace {
+    type Output = ();
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        self.trivia.skip_input(input)
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("whitespace".to_string())
+    }
+}
+
+/// Run some parser, and then skip any trivia that follows it, see `TokenParser`
+pub struct Lexeme<P>
+where
+    P: Parser,
+{
+    parser: P,
+    trivia: TokenParser,
+}
+
+impl<P> Parser for Lexeme<P>
+where
+    P: Parser,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        let ((), rest) = self.trivia.skip_input(&rest)?;
+        Ok((parsed, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod lexeme_test {
+    use super::{identifier, keyword, TokenParser};
+    use crate::{
+        errors::ParsingErrorKind,
+        inputs::Input,
+        parsers::{repeat_p::RepeatParser, ParseMatch},
+        traits::Parser,
+    };
+
+    #[test]
+    fn keywords() {
+        let (p, rest) = keyword("let").parse(&"let(".into()).unwrap();
+        assert_eq!(p, "let");
+        assert_eq!(rest.source, "(");
+        assert!(keyword("let").parse(&"let".into()).is_ok());
+
+        for source in ["letter", "let_", "let1", "le"] {
+            let err = keyword("let").parse(&source.into()).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                &ParsingErrorKind::Expected("keyword \"let\"".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn identifiers() {
+        let (p, rest) = identifier().parse(&"_foo1 bar".into()).unwrap();
+        assert_eq!(p, "_foo1");
+        assert_eq!(rest.source, " bar");
+
+        let (p, rest) = identifier()
+            .parse(&"\u{3b1}\u{3b2}\u{3b3}+1".into())
+            .unwrap();
+        assert_eq!(p, "\u{3b1}\u{3b2}\u{3b3}");
+        assert_eq!(rest.source, "+1");
+        assert_eq!(rest.col, 3);
+
+        assert!(identifier().parse(&"1abc".into()).is_err());
+        assert!(identifier().parse(&"".into()).is_err());
+    }
+
+    #[test]
+    fn reserved_words() {
+        let ident = identifier().reserved(&["if", "else"]).reserved(&["while"]);
+        for word in ["if", "else", "while"] {
+            let err = ident.parse(&format!("{word} ").into()).unwrap_err();
+            assert_eq!(
+                err.kind(),
+                &ParsingErrorKind::ReservedWord(word.to_string())
+            );
+            assert_eq!(err.col(), 0);
+        }
+        assert_eq!(ident.parse(&"elsewhere".into()).unwrap().0, "elsewhere");
+    }
+
+    #[test]
+    fn whitespace_only_lexemes() {
+        let words = RepeatParser::new(identifier().lexeme());
+        let (p, rest) = words.parse(&"one \t two\n three".into()).unwrap();
+        assert_eq!(p, vec!["one", "two", "three"]);
+        assert!(rest.source.is_empty());
+
+        // Comments are not trivia unless they are configured
+        let (_, rest) = ParseMatch("a").lexeme().parse(&"a // b".into()).unwrap();
+        assert_eq!(rest.source, "// b");
+    }
+
+    #[test]
+    fn comments() {
+        let tokens = TokenParser::new()
+            .line_comment("#")
+            .block_comment("(*", "*)");
+        let (_, rest) = tokens
+            .whitespace()
+            .parse(&" # one\n (* two *)\n# three\nx".into())
+            .unwrap();
+        assert_eq!(rest.source, "x");
+
+        let (_, rest) = tokens.whitespace().parse(&"# eof".into()).unwrap();
+        assert!(rest.source.is_empty());
+
+        let err = tokens
+            .whitespace()
+            .parse(&"  (* never closed".into())
+            .unwrap_err();
+        assert_eq!(err.col(), 2);
+    }
+
+    #[test]
+    fn partial_input() {
+        let whitespace = TokenParser::new()
+            .line_comment("//")
+            .block_comment("/*", "*/")
+            .whitespace();
+        let partial = |source: &str| Input::from(source).partial(true);
+
+        // The comment, or the start of one, may go on in the next part of the input
+        for source in [" // to be continued", " /* open", " /"] {
+            assert!(
+                whitespace
+                    .parse(&partial(source))
+                    .is_err_and(|e| e.is_incomplete()),
+                "{source}"
+            );
+        }
+
+        let (_, rest) = whitespace.parse(&partial(" // done\nx")).unwrap();
+        assert_eq!(rest.source, "x");
+        let (_, rest) = whitespace.parse(&partial(" x /")).unwrap();
+        assert_eq!(rest.source, "x /");
+        assert!(whitespace.parse(&partial("  ")).is_ok());
+        assert!(whitespace.parse(&"// eof".into()).is_ok());
+    }
+
+    #[test]
+    fn nested_comments() {
+        let source = "/* a /* b */ c */ x";
+
+        let flat = TokenParser::new().block_comment("/*", "*/");
+        let (_, rest) = flat.whitespace().parse(&source.into()).unwrap();
+        assert_eq!(rest.source, "c */ x");
+
+        let nested = flat.nested_comments(true);
+        let (_, rest) = nested.whitespace().parse(&source.into()).unwrap();
+        assert_eq!(rest.source, "x");
+
+        assert!(nested.whitespace().parse(&"/* /* */".into()).is_err());
+    }
+
+    #[test]
+    fn symbols_skip_trailing_trivia() {
+        let tokens = TokenParser::new().line_comment("--");
+        let call = tokens
+            .symbol("(")
+            .and_then(tokens.lexeme(identifier()))
+            .and_then(tokens.symbol(")"));
+        let (((_, name), _), rest) = call
+            .parse(&"( -- open\n f -- name\n ) rest".into())
+            .unwrap();
+        assert_eq!(name, "f");
+        assert_eq!(rest.source, "rest");
+    }
+}
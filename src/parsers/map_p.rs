@@ -1,51 +1,93 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, marker::PhantomData};
 
 use crate::{
+    describe::Description,
     errors::{ParsingError, ParsingErrorKind},
-    inputs::Input,
+    inputs::{Input, ParserInput},
     traits::Parser,
     type_alias::ParserRes,
 };
 
 /// Given a parser with output of type K, and a mapping K -> Z,
 /// make a new parser with output of type Z
-pub struct MapParser<'a, P: Parser, T> {
+pub struct MapParser<'a, P: Parser<I>, T, I: ParserInput = Input> {
     pub parser: P,
     pub mapping: &'a dyn Fn(P::Output) -> T,
+    input: PhantomData<I>,
 }
 
-pub struct TryMapParser<'a, P: Parser, T> {
+pub struct TryMapParser<'a, P: Parser<I>, T, I: ParserInput = Input> {
     pub parser: P,
     pub try_map: &'a dyn Fn(P::Output) -> Option<T>,
+    input: PhantomData<I>,
 }
 
-impl<'a, P, T> Parser for MapParser<'a, P, T>
+impl<'a, P, T, I> MapParser<'a, P, T, I>
 where
-    P: Parser,
+    P: Parser<I>,
+    I: ParserInput,
+{
+    pub fn new(parser: P, mapping: &'a dyn Fn(P::Output) -> T) -> Self {
+        MapParser {
+            parser,
+            mapping,
+            input: PhantomData,
+        }
+    }
+}
+
+impl<'a, P, T, I> TryMapParser<'a, P, T, I>
+where
+    P: Parser<I>,
+    I: ParserInput,
+{
+    pub fn new(parser: P, try_map: &'a dyn Fn(P::Output) -> Option<T>) -> Self {
+        TryMapParser {
+            parser,
+            try_map,
+            input: PhantomData,
+        }
+    }
+}
+
+impl<'a, P, T, I> Parser<I> for MapParser<'a, P, T, I>
+where
+    P: Parser<I>,
     T: Debug,
+    I: ParserInput,
 {
     type Output = T;
-    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
-        self.parser.parse_and_then_map(input, &self.mapping)
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        self.parser.parse_and_then_map(input, self.mapping)
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
     }
 }
 
-impl<'a, P, T> Parser for TryMapParser<'a, P, T>
+impl<'a, P, T, I> Parser<I> for TryMapParser<'a, P, T, I>
 where
-    P: Parser,
+    P: Parser<I>,
     T: Debug,
+    I: ParserInput,
 {
     type Output = T;
-    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
         let (p, rest) = self.parser.parse(input)?;
         match (self.try_map)(p) {
             None => {
                 let kind = ParsingErrorKind::MappingError(
                     "Parsing worked, but mapping failed".to_string(),
                 );
-                Err(ParsingError::new(kind, rest.line, rest.col))
+                let at = rest.position();
+                Err(ParsingError::new(kind, at.line, at.col))
             }
             Some(mapped_val) => Ok((mapped_val, rest)),
         }
     }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
 }
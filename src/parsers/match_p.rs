@@ -0,0 +1,328 @@
+#[cfg(feature = "caseless")]
+use caseless::Caseless;
+#[cfg(feature = "unicode-normalization")]
+use unicode_normalization::{char::canonical_combining_class, UnicodeNormalization};
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+use super::ParseMatch;
+
+/// How a `ParseMatchWith` parser compares the input to its literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Ignore the case of ascii letters, so "select" matches "SeLeCt"
+    AsciiCaseInsensitive,
+    /// Compare using unicode default case folding, so "strasse" matches "STRAßE". Needs
+    /// the `caseless` feature.
+    #[cfg(feature = "caseless")]
+    CaseFold,
+    /// Compare canonically equivalent text (NFC), so "é" matches "e\u{301}". Needs the
+    /// `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    Nfc,
+    /// Compare compatibility equivalent text (NFKC), so "fi" matches "\u{fb01}". Needs
+    /// the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    Nfkc,
+}
+
+impl MatchMode {
+    fn describe(&self) -> &'static str {
+        match self {
+            MatchMode::AsciiCaseInsensitive => "ignoring ascii case",
+            #[cfg(feature = "caseless")]
+            MatchMode::CaseFold => "ignoring case",
+            #[cfg(feature = "unicode-normalization")]
+            MatchMode::Nfc => "under NFC normalisation",
+            #[cfg(feature = "unicode-normalization")]
+            MatchMode::Nfkc => "under NFKC normalisation",
+        }
+    }
+
+    /// Whether all of `source` is the start of some text that matches `literal`, so that
+    /// more input could change the result. Under normalisation, this includes a complete
+    /// match, since a combining mark could still follow it.
+    fn could_continue(&self, source: &str, literal: &str) -> bool {
+        match self {
+            MatchMode::AsciiCaseInsensitive => {
+                source.chars().count() < literal.chars().count()
+                    && source
+                        .chars()
+                        .zip(literal.chars())
+                        .all(|(c, expected)| c.eq_ignore_ascii_case(&expected))
+            }
+            #[cfg(feature = "caseless")]
+            MatchMode::CaseFold => {
+                let source = caseless::default_case_fold_str(source);
+                let target = caseless::default_case_fold_str(literal);
+                source.len() < target.len() && target.starts_with(&source)
+            }
+            #[cfg(feature = "unicode-normalization")]
+            MatchMode::Nfc => literal
+                .nfd()
+                .collect::<String>()
+                .starts_with(&source.nfd().collect::<String>()),
+            #[cfg(feature = "unicode-normalization")]
+            MatchMode::Nfkc => literal
+                .nfkd()
+                .collect::<String>()
+                .starts_with(&source.nfkd().collect::<String>()),
+        }
+    }
+
+    /// Find how many bytes at the start of `source` match `literal`
+    fn matched_len(&self, source: &str, literal: &str) -> Option<usize> {
+        match self {
+            MatchMode::AsciiCaseInsensitive => {
+                let mut len = 0;
+                let mut chars = source.chars();
+                for expected in literal.chars() {
+                    let c = chars.next()?;
+                    if !c.eq_ignore_ascii_case(&expected) {
+                        return None;
+                    }
+                    len += c.len_utf8();
+                }
+                Some(len)
+            }
+            #[cfg(feature = "caseless")]
+            MatchMode::CaseFold => {
+                // Case folding works on single characters, so the source can be folded
+                // one character at a time until the whole literal has been matched
+                let target = caseless::default_case_fold_str(literal);
+                let mut matched = 0;
+                for (i, c) in source.char_indices() {
+                    if matched == target.len() {
+                        return Some(i);
+                    }
+                    let folded: String = std::iter::once(c).default_case_fold().collect();
+                    if !target[matched..].starts_with(&folded) {
+                        return None;
+                    }
+                    matched += folded.len();
+                }
+                (matched == target.len()).then_some(source.len())
+            }
+            #[cfg(feature = "unicode-normalization")]
+            MatchMode::Nfc | MatchMode::Nfkc => {
+                let compatibility = *self == MatchMode::Nfkc;
+                let decompose = |s: &str| -> String {
+                    if compatibility {
+                        s.nfkd().collect()
+                    } else {
+                        s.nfd().collect()
+                    }
+                };
+                let target = decompose(literal);
+                // Decomposing never removes characters, so the matching part of the
+                // source cannot have more characters than the decomposed literal
+                let limit = target.chars().count();
+                let mut ends = source.char_indices().map(|(i, _)| i).skip(1);
+                let ends = (0..limit).map(|_| ends.next().unwrap_or(source.len()));
+                for end in ends {
+                    // A combining mark after the match would change the last character
+                    let next_is_starter = source[end..]
+                        .chars()
+                        .next()
+                        .is_none_or(|c| canonical_combining_class(c) == 0);
+                    if next_is_starter && decompose(&source[..end]) == target {
+                        return Some(end);
+                    }
+                }
+                target.is_empty().then_some(0)
+            }
+        }
+    }
+}
+
+/// A parser that will parse a literal string, comparing it to the input in some
+/// `MatchMode` other than exact equality.
+///
+/// The output is the text as it appears in the input, not the literal.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::ParseMatch;
+/// use parlib::traits::Parser;
+///
+/// let select = ParseMatch("select").ascii_case_insensitive();
+/// let (parsed, rest) = select.parse(&"SELECT *".into()).unwrap();
+/// assert_eq!(parsed, "SELECT".to_string());
+/// assert_eq!(rest.source, " *".to_string());
+/// ```
+pub struct ParseMatchWith<A>
+where
+    A: Into<String>,
+{
+    literal: A,
+    mode: MatchMode,
+}
+
+impl<A> ParseMatchWith<A>
+where
+    A: Into<String>,
+{
+    pub fn new(literal: A, mode: MatchMode) -> Self {
+        ParseMatchWith { literal, mode }
+    }
+}
+
+impl<A> ParseMatch<A>
+where
+    A: Into<String>,
+{
+    /// Match the literal ignoring the case of ascii letters
+    pub fn ascii_case_insensitive(self) -> ParseMatchWith<A> {
+        ParseMatchWith::new(self.0, MatchMode::AsciiCaseInsensitive)
+    }
+
+    /// Match the literal using unicode case folding
+    #[cfg(feature = "caseless")]
+    pub fn case_fold(self) -> ParseMatchWith<A> {
+        ParseMatchWith::new(self.0, MatchMode::CaseFold)
+    }
+
+    /// Match any text that is canonically equivalent to the literal
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfc(self) -> ParseMatchWith<A> {
+        ParseMatchWith::new(self.0, MatchMode::Nfc)
+    }
+
+    /// Match any text that is compatibility equivalent to the literal
+    #[cfg(feature = "unicode-normalization")]
+    pub fn nfkc(self) -> ParseMatchWith<A> {
+        ParseMatchWith::new(self.0, MatchMode::Nfkc)
+    }
+}
+
+impl<A> Parser for ParseMatchWith<A>
+where
+    A: Into<String> + Clone,
+{
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let literal: String = self.literal.clone().into();
+        if input.is_partial() && self.mode.could_continue(&input.source, &literal) {
+            return Err(input.incomplete(Needed::Unknown));
+        }
+        let Some(len) = self.mode.matched_len(&input.source, &literal) else {
+            let kind = ParsingErrorKind::Expected(format!("{literal:?} {}", self.mode.describe()));
+            return Err(ParsingError::new(kind, input.line, input.col));
+        };
+
+        let matched = input.source[..len].to_string();
+        let rest = input.clone().char_offset(matched.chars().count());
+        Ok((matched, rest))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Literal(self.literal.clone().into())
+    }
+}
+
+#[cfg(test)]
+mod test_match_with {
+    use crate::{errors::ParsingErrorKind, inputs::Input, parsers::ParseMatch, traits::Parser};
+
+    #[test]
+    fn ascii_case_insensitive() {
+        let select = ParseMatch("select").ascii_case_insensitive();
+        let (p, rest) = select.parse(&"SeLeCt 1".into()).unwrap();
+        assert_eq!(p, "SeLeCt");
+        assert_eq!(rest.source, " 1");
+        assert_eq!(rest.col, 6);
+
+        assert!(select.parse(&"sel".into()).is_err());
+        // Only ascii letters are case insensitive
+        assert!(ParseMatch("\u{e9}")
+            .ascii_case_insensitive()
+            .parse(&"\u{c9}".into())
+            .is_err());
+
+        let err = select.parse(&"insert".into()).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("\"select\" ignoring ascii case".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "caseless")]
+    fn case_fold() {
+        let strasse = ParseMatch("strasse").case_fold();
+        let (p, rest) = strasse.parse(&"STRA\u{df}E!".into()).unwrap();
+        assert_eq!(p, "STRA\u{df}E");
+        assert_eq!(rest.source, "!");
+        assert_eq!(rest.col, 6);
+
+        let (p, _) = ParseMatch("\u{3a3}\u{391}")
+            .case_fold()
+            .parse(&"\u{3c3}\u{3b1}".into())
+            .unwrap();
+        assert_eq!(p, "\u{3c3}\u{3b1}");
+
+        // A folded character may not be split across the end of the literal
+        assert!(ParseMatch("s").case_fold().parse(&"\u{df}".into()).is_err());
+        assert!(strasse.parse(&"strand".into()).is_err());
+
+        assert!(strasse
+            .parse(&Input::from("STRA\u{df}").partial(true))
+            .is_err_and(|e| e.is_incomplete()));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn normalised() {
+        let cafe = ParseMatch("caf\u{e9}").nfc();
+        let (p, rest) = cafe.parse(&"cafe\u{301} au lait".into()).unwrap();
+        assert_eq!(p, "cafe\u{301}");
+        assert_eq!(rest.source, " au lait");
+        assert_eq!(rest.col, 5);
+
+        let (p, _) = ParseMatch("cafe\u{301}")
+            .nfc()
+            .parse(&"caf\u{e9}".into())
+            .unwrap();
+        assert_eq!(p, "caf\u{e9}");
+
+        // The combining accent belongs to the e, so "cafe" does not match
+        assert!(ParseMatch("cafe")
+            .nfc()
+            .parse(&"cafe\u{301}".into())
+            .is_err());
+
+        // Compatibility equivalence only holds under NFKC
+        assert!(ParseMatch("fi").nfc().parse(&"\u{fb01}x".into()).is_err());
+        let (p, rest) = ParseMatch("fi").nfkc().parse(&"\u{fb01}x".into()).unwrap();
+        assert_eq!(p, "\u{fb01}");
+        assert_eq!(rest.source, "x");
+
+        // A combining mark in the next part of the input would change the last character
+        let cafe = ParseMatch("cafe").nfc();
+        assert!(cafe
+            .parse(&Input::from("cafe").partial(true))
+            .is_err_and(|e| e.is_incomplete()));
+        assert!(cafe.parse(&Input::from("cafe ").partial(true)).is_ok());
+        assert!(cafe.parse(&"cafe".into()).is_ok());
+    }
+
+    #[test]
+    fn partial_input() {
+        let partial = |source: &str| Input::from(source).partial(true);
+        let incomplete = |e: &crate::errors::ParsingError| e.is_incomplete();
+
+        let select = ParseMatch("select").ascii_case_insensitive();
+        assert!(select.parse(&partial("SEL")).is_err_and(|e| incomplete(&e)));
+        assert!(select.parse(&partial("SELECT")).is_ok());
+        assert!(select
+            .parse(&partial("SET"))
+            .is_err_and(|e| !incomplete(&e)));
+    }
+}
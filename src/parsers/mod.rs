@@ -1,11 +1,27 @@
 pub mod and_p;
+pub mod char_p;
+pub mod cst_p;
+pub mod indent_p;
+pub mod iter_p;
+pub mod lexeme_p;
 pub mod map_p;
+pub mod match_p;
+pub mod named_p;
+pub mod number_p;
 pub mod or_p;
+#[cfg(feature = "regex")]
+pub mod regex_p;
 pub mod repeat_p;
+pub mod span_p;
+pub mod state_p;
 pub mod string_p;
+pub mod token_p;
+pub mod trace_p;
+pub mod trie_p;
 
 use crate::{
-    errors::{ParsingError, ParsingErrorKind},
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
     inputs::Input,
     traits::Parser,
     type_alias::ParserRes,
@@ -35,6 +51,12 @@ where
     type Output = String;
     fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
         let match_str: String = self.0.clone().into();
+        if input.is_partial()
+            && input.source.len() < match_str.len()
+            && match_str.starts_with(input.source.as_str())
+        {
+            return Err(input.incomplete(Needed::Size(match_str.len() - input.source.len())));
+        }
         if !input.source.starts_with(&match_str) {
             let kind = ParsingErrorKind::PatternNotFound(format!(
                 "{} did not match pattern: {}",
@@ -43,9 +65,13 @@ where
             return Err(ParsingError::new(kind, input.line, input.col));
         }
 
-        let rest = input.clone().char_offset(match_str.len());
+        let rest = input.clone().char_offset(match_str.chars().count());
         Ok((match_str, rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::Literal(self.0.clone().into())
+    }
 }
 
 /// Parse a character if a predicate is met, otherwise, return an error.
@@ -68,12 +94,19 @@ impl Parser for ParseIf {
     type Output = char;
     fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
         let maybe_first_char = input.source.chars().next();
+        if maybe_first_char.is_none() && input.is_partial() {
+            return Err(input.incomplete(Needed::Size(1)));
+        }
         if let Some(true) = maybe_first_char.map(self.0) {
             return Ok((maybe_first_char.unwrap(), input.clone().char_offset(1)));
         }
         let kind = ParsingErrorKind::PatternNotFound("if predicate not met".to_string());
         Err(ParsingError::new(kind, input.line, input.col))
     }
+
+    fn describe(&self) -> Description {
+        Description::Class("character".to_string())
+    }
 }
 
 /// Keep parsing characters while some predicate is met. If none of the characters
@@ -121,9 +154,16 @@ where
             .chars()
             .take_while(|&x| self.0(x))
             .collect::<String>();
-        let len = taken.len();
+        if input.is_partial() && taken.len() == input.source.len() {
+            return Err(input.incomplete(Needed::Unknown));
+        }
+        let len = taken.chars().count();
         Ok((taken, input.clone().char_offset(len)))
     }
+
+    fn describe(&self) -> Description {
+        Description::repeat(Description::Class("character".to_string()), 0, None)
+    }
 }
 /// Keep parsing characters while some predicate is met. If none of the characters
 /// meet the predicate, and error will be returned. If this is not desired, try
@@ -165,20 +205,32 @@ where
     type Output = String;
     fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
         let taken: String = input.source.chars().take_while(|&x| self.0(x)).collect();
+        // More matching characters could follow the end of a partial input
+        if input.is_partial() && taken.len() == input.source.len() {
+            return Err(input.incomplete(Needed::Unknown));
+        }
         if taken.is_empty() {
             let kind =
                 ParsingErrorKind::PatternNotFound("no characters matched predicate".to_string());
             return Err(ParsingError::new(kind, input.line, input.col));
         }
-        let len = taken.len();
+        let len = taken.chars().count();
         Ok((taken, input.clone().char_offset(len)))
     }
+
+    fn describe(&self) -> Description {
+        Description::repeat(Description::Class("character".to_string()), 1, None)
+    }
 }
 
 #[cfg(test)]
 mod test_base_parsers {
-    use super::{ParseIf, ParseMatch, ParseWhile};
-    use crate::traits::Parser;
+    use super::{ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing};
+    use crate::{
+        errors::{Needed, ParsingErrorKind},
+        inputs::Input,
+        traits::Parser,
+    };
 
     #[test]
     fn match_parser() {
@@ -188,6 +240,17 @@ mod test_base_parsers {
         assert_eq!(i.source, " and".to_string());
     }
 
+    #[test]
+    fn match_non_ascii() {
+        // The rest starts after the characters of the literal, not after as many bytes
+        let (p, rest) = ParseMatch("caf\u{e9}")
+            .parse(&"caf\u{e9} cr\u{e8}me".into())
+            .unwrap();
+        assert_eq!(p, "caf\u{e9}");
+        assert_eq!(rest.source, " cr\u{e8}me");
+        assert_eq!((rest.col, rest.offset), (4, 5));
+    }
+
     #[test]
     fn if_parser() {
         let parse_if = ParseIf(|c| c.is_numeric());
@@ -196,6 +259,45 @@ mod test_base_parsers {
         assert_eq!(i.source, "2hello".to_string());
     }
 
+    #[test]
+    fn positions_over_lines() {
+        // Every new line that a parser consumes moves the rest to the start of a new line
+        let (_, rest) = ParseMatch("a\nb").parse(&"a\nbc".into()).unwrap();
+        assert_eq!((rest.line, rest.col, rest.offset), (1, 1, 3));
+
+        let blank = ParseWhile(|c| c.is_whitespace());
+        let (_, rest) = blank.parse(&" \n\n  x".into()).unwrap();
+        assert_eq!((rest.line, rest.col, rest.offset), (2, 2, 5));
+        assert_eq!(rest.source, "x");
+    }
+
+    #[test]
+    fn partial_input() {
+        let input = Input::from("whi").partial(true);
+        let err = ParseMatch("while").parse(&input).unwrap_err();
+        assert_eq!(err.kind(), &ParsingErrorKind::Incomplete(Needed::Size(2)));
+        assert!(ParseMatch("if")
+            .parse(&input)
+            .is_err_and(|e| !e.is_incomplete()));
+        // All of the literal is there, even if nothing follows it yet
+        let (p, rest) = ParseMatch("while")
+            .parse(&Input::from("while").partial(true))
+            .unwrap();
+        assert_eq!(p, "while");
+        assert!(rest.source.is_empty());
+
+        // The digits may continue in the next part of the input
+        let digits = ParseWhile(|c| c.is_numeric());
+        assert!(digits
+            .parse(&Input::from("123").partial(true))
+            .is_err_and(|e| e.is_incomplete()));
+        assert!(digits.parse(&Input::from("123 ").partial(true)).is_ok());
+        assert!(digits.parse(&Input::from("123")).is_ok());
+        assert!(ParseIf(|c| c.is_numeric())
+            .parse(&Input::from("").partial(true))
+            .is_err_and(|e| e.is_incomplete()));
+    }
+
     #[test]
     fn parse_while() {
         let parse_numbers = ParseWhile(|c| c.is_numeric());
@@ -207,4 +309,20 @@ mod test_base_parsers {
         let answer_bad = parse_numbers.parse(&"x123a 1234".into());
         assert!(answer_bad.is_err());
     }
+
+    #[test]
+    fn while_non_ascii() {
+        let letters = ParseWhile(char::is_alphabetic);
+        let (p, rest) = letters.parse(&"\u{e9}t\u{e9} 2024".into()).unwrap();
+        assert_eq!(p, "\u{e9}t\u{e9}");
+        assert_eq!(rest.source, " 2024");
+        assert_eq!((rest.col, rest.offset), (3, 5));
+
+        let (p, rest) = ParseWhileOrNothing(|c| c != ' ')
+            .parse(&"\u{3bb}x.y z".into())
+            .unwrap();
+        assert_eq!(p, "\u{3bb}x.y");
+        assert_eq!(rest.source, " z");
+        assert_eq!((rest.col, rest.offset), (4, 5));
+    }
 }
@@ -0,0 +1,36 @@
+use crate::{
+    describe::Description, errors::ParsingError, inputs::ParserInput, traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Give some parser a name, so that it is described as a rule of the grammar (see
+/// `describe::Grammar`). This does not change what the parser parses.
+#[derive(Debug, Clone)]
+pub struct Named<P> {
+    parser: P,
+    name: String,
+}
+
+impl<P> Named<P> {
+    pub fn new(parser: P, name: &str) -> Self {
+        Named {
+            parser,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl<I, P> Parser<I> for Named<P>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        self.parser.parse(input)
+    }
+
+    fn describe(&self) -> Description {
+        Description::rule(&self.name, self.parser.describe())
+    }
+}
@@ -0,0 +1,506 @@
+use std::{any::type_name, fmt::Debug, marker::PhantomData, str::FromStr};
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// The primitive integer types that can be parsed by `IntegerParser`
+pub trait Integer: Copy + Debug {
+    const ZERO: Self;
+
+    /// Compute `self * radix + digit` (or `self * radix - digit` for negative numbers),
+    /// returning `None` on overflow
+    fn push_digit(self, radix: u32, digit: u32, negative: bool) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                const ZERO: Self = 0;
+
+                fn push_digit(self, radix: u32, digit: u32, negative: bool) -> Option<Self> {
+                    let radix = Self::try_from(radix).ok()?;
+                    let digit = Self::try_from(digit).ok()?;
+                    let shifted = self.checked_mul(radix)?;
+                    if negative {
+                        shifted.checked_sub(digit)
+                    } else {
+                        shifted.checked_add(digit)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Parse an integer into any of the primitive integer types.
+///
+/// Only ASCII digits are accepted. A leading `-` is always allowed, but negative numbers
+/// will give a `NumberOutOfRange` error for unsigned types, as will any number that does
+/// not fit in the type.
+///
+/// Optionally, the parser can also accept:
+///
+/// - A leading `+` (`plus_sign`)
+/// - The `0x`, `0o` and `0b` prefixes (in either case) for hex, octal and binary numbers (`radix_prefixes`)
+/// - A separator between digits, such as `1_000_000` (`separator`)
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::number_p::IntegerParser;
+/// use parlib::traits::Parser;
+///
+/// let ip = IntegerParser::<i16>::new().radix_prefixes(true).separator('_');
+/// let (parsed, _) = ip.parse(&"-0x7f_ff".into()).unwrap();
+/// assert_eq!(parsed, -0x7fff);
+///
+/// assert!(IntegerParser::<u8>::new().parse(&"256".into()).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct IntegerParser<T>
+where
+    T: Integer,
+{
+    plus_sign: bool,
+    radix_prefixes: bool,
+    separator: Option<char>,
+    output: PhantomData<T>,
+}
+
+impl<T> Default for IntegerParser<T>
+where
+    T: Integer,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntegerParser<T>
+where
+    T: Integer,
+{
+    /// By default, only decimal numbers with an optional `-` are accepted
+    pub fn new() -> Self {
+        IntegerParser {
+            plus_sign: false,
+            radix_prefixes: false,
+            separator: None,
+            output: PhantomData,
+        }
+    }
+
+    /// Allow a leading `+`
+    pub fn plus_sign(mut self, allow: bool) -> Self {
+        self.plus_sign = allow;
+        self
+    }
+
+    /// Allow the `0x`, `0o` and `0b` prefixes, in either case
+    pub fn radix_prefixes(mut self, allow: bool) -> Self {
+        self.radix_prefixes = allow;
+        self
+    }
+
+    /// Allow some character to separate digits. The separator must be followed by a digit.
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = Some(separator);
+        self
+    }
+}
+
+impl<T> Parser for IntegerParser<T>
+where
+    T: Integer,
+{
+    type Output = T;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let source = input.source.as_str();
+        let error_at = |kind, offset| {
+            let at = input.clone().char_offset(offset);
+            ParsingError::new(kind, at.line, at.col)
+        };
+
+        // All of the characters in a number are ascii, so byte offsets are char offsets
+        let mut i = 0;
+        let negative = match source.as_bytes().first() {
+            Some(b'-') => {
+                i += 1;
+                true
+            }
+            Some(b'+') if self.plus_sign => {
+                i += 1;
+                false
+            }
+            _ => false,
+        };
+
+        let mut radix = 10;
+        if self.radix_prefixes {
+            for (prefix, prefix_radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+                let found = source.get(i..i + prefix.len());
+                if !found.is_some_and(|found| found.eq_ignore_ascii_case(prefix)) {
+                    continue;
+                }
+                // Without a digit after it, the prefix is only a 0 followed by a letter
+                let after = i + prefix.len();
+                if input.is_partial() && after == source.len() {
+                    return Err(input.incomplete(Needed::Unknown));
+                }
+                if source[after..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_digit(prefix_radix))
+                {
+                    radix = prefix_radix;
+                    i = after;
+                }
+                break;
+            }
+        }
+
+        let mut value = T::ZERO;
+        let mut overflow = false;
+        let mut digits = 0;
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_digit(radix));
+        loop {
+            let mut rest = source[i..].chars();
+            let next = rest.next();
+            match next.and_then(|c| c.to_digit(radix)) {
+                Some(digit) => {
+                    match value.push_digit(radix, digit, negative) {
+                        Some(v) => value = v,
+                        None => overflow = true,
+                    }
+                    digits += 1;
+                    i += 1;
+                }
+                None if digits > 0 && next == self.separator && is_digit(rest.next()) => {
+                    i += next.map_or(0, char::len_utf8);
+                }
+                None => break,
+            }
+        }
+
+        // The number may continue in the next part of a partial input
+        if input.is_partial() && i == source.len() {
+            return Err(input.incomplete(Needed::Unknown));
+        }
+
+        if digits == 0 {
+            let kind = ParsingErrorKind::PatternNotFound(format!(
+                "expected digits of a base {radix} integer"
+            ));
+            return Err(error_at(kind, source[..i].chars().count()));
+        }
+
+        let consumed = source[..i].chars().count();
+        if overflow {
+            let kind = ParsingErrorKind::NumberOutOfRange(format!(
+                "{} does not fit in {}",
+                &source[..i],
+                type_name::<T>()
+            ));
+            return Err(error_at(kind, 0));
+        }
+
+        Ok((value, input.clone().char_offset(consumed)))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("integer".to_string())
+    }
+}
+
+/// Parse a floating point number, following the grammar for numbers in JSON (RFC 8259)
+///
+/// ```text
+/// number = [ "-" ] ( "0" / [1-9] *DIGIT ) [ "." 1*DIGIT ] [ ( "e" / "E" ) [ "+" / "-" ] 1*DIGIT ]
+/// ```
+///
+/// A `.` or an exponent that is not followed by a digit gives an error.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::number_p::FloatParser;
+/// use parlib::traits::Parser;
+///
+/// let (parsed, rest) = FloatParser::<f64>::new().parse(&"-12.5e-1, 3".into()).unwrap();
+/// assert_eq!(parsed, -1.25);
+/// assert_eq!(rest.source, ", 3".to_string());
+///
+/// assert!(FloatParser::<f64>::new().parse(&"1.".into()).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct FloatParser<T = f64>
+where
+    T: FromStr + Debug,
+{
+    output: PhantomData<T>,
+}
+
+impl<T> Default for FloatParser<T>
+where
+    T: FromStr + Debug,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FloatParser<T>
+where
+    T: FromStr + Debug,
+{
+    pub fn new() -> Self {
+        FloatParser {
+            output: PhantomData,
+        }
+    }
+}
+
+impl<T> Parser for FloatParser<T>
+where
+    T: FromStr + Debug,
+{
+    type Output = T;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let bytes = input.source.as_bytes();
+        let error_at = |message: &str, offset| {
+            if input.is_partial() && offset == bytes.len() {
+                return input.incomplete(Needed::Unknown);
+            }
+            let kind = ParsingErrorKind::PatternNotFound(message.to_string());
+            let at = input.clone().char_offset(offset);
+            ParsingError::new(kind, at.line, at.col)
+        };
+        let digits_from = |start: usize| {
+            start
+                + bytes[start..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count()
+        };
+
+        let mut i = 0;
+        if bytes.first() == Some(&b'-') {
+            i += 1;
+        }
+
+        // The whole part may not have leading zeros
+        match bytes.get(i) {
+            Some(b'0') => i += 1,
+            Some(b'1'..=b'9') => i = digits_from(i),
+            _ => return Err(error_at("expected a digit", i)),
+        }
+
+        if bytes.get(i) == Some(&b'.') {
+            let end = digits_from(i + 1);
+            if end == i + 1 {
+                return Err(error_at("expected a digit after the decimal point", end));
+            }
+            i = end;
+        }
+
+        if let Some(b'e' | b'E') = bytes.get(i) {
+            i += 1;
+            if let Some(b'+' | b'-') = bytes.get(i) {
+                i += 1;
+            }
+            let end = digits_from(i);
+            if end == i {
+                return Err(error_at("expected a digit in the exponent", end));
+            }
+            i = end;
+        }
+
+        if input.is_partial() && i == bytes.len() {
+            return Err(input.incomplete(Needed::Unknown));
+        }
+
+        // The number has been validated, so this can only fail if `T` is not a float
+        let number = input.source[..i].parse::<T>().map_err(|_| {
+            let kind = ParsingErrorKind::NumberOutOfRange(format!(
+                "{} is not a valid {}",
+                &input.source[..i],
+                type_name::<T>()
+            ));
+            ParsingError::new(kind, input.line, input.col)
+        })?;
+        Ok((number, input.clone().char_offset(i)))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("number".to_string())
+    }
+}
+
+/// Parse a decimal integer, see `IntegerParser`
+pub fn integer<T>() -> IntegerParser<T>
+where
+    T: Integer,
+{
+    IntegerParser::new()
+}
+
+/// Parse a JSON style floating point number, see `FloatParser`
+pub fn float() -> FloatParser<f64> {
+    FloatParser::new()
+}
+
+#[cfg(test)]
+mod number_parser_test {
+    use super::{float, integer, FloatParser, IntegerParser};
+    use crate::errors::ParsingErrorKind;
+    use crate::inputs::Input;
+    use crate::traits::Parser;
+
+    #[test]
+    fn decimal_integers() {
+        let (p, rest) = integer::<i32>().parse(&"123abc".into()).unwrap();
+        assert_eq!(p, 123);
+        assert_eq!(rest.source, "abc");
+        assert_eq!(rest.col, 3);
+
+        assert_eq!(integer::<i32>().parse(&"-42".into()).unwrap().0, -42);
+        assert_eq!(integer::<u64>().parse(&"007".into()).unwrap().0, 7);
+        assert!(integer::<i32>().parse(&"+1".into()).is_err());
+        assert_eq!(
+            integer::<i32>()
+                .plus_sign(true)
+                .parse(&"+1".into())
+                .unwrap()
+                .0,
+            1
+        );
+    }
+
+    #[test]
+    fn only_ascii_digits() {
+        assert!(integer::<i32>().parse(&"\u{663}".into()).is_err());
+        assert!(integer::<i32>().parse(&"-".into()).is_err());
+        assert!(integer::<i32>().parse(&"".into()).is_err());
+    }
+
+    #[test]
+    fn overflow() {
+        assert_eq!(integer::<i8>().parse(&"127".into()).unwrap().0, 127);
+        assert_eq!(integer::<i8>().parse(&"-128".into()).unwrap().0, -128);
+        assert_eq!(integer::<u8>().parse(&"-0".into()).unwrap().0, 0);
+
+        for (source, err) in [
+            ("128", integer::<i8>().parse(&"128".into()).unwrap_err()),
+            ("-129", integer::<i8>().parse(&"-129".into()).unwrap_err()),
+            ("-1", integer::<u8>().parse(&"-1".into()).unwrap_err()),
+            (
+                "99999999999999999999999",
+                integer::<u64>()
+                    .parse(&"99999999999999999999999".into())
+                    .unwrap_err(),
+            ),
+        ] {
+            assert!(
+                matches!(err.kind(), ParsingErrorKind::NumberOutOfRange(_)),
+                "{source} should overflow"
+            );
+            assert_eq!(err.col(), 0);
+        }
+
+        assert_eq!(
+            integer::<i128>()
+                .parse(&"-170141183460469231731687303715884105728".into())
+                .unwrap()
+                .0,
+            i128::MIN
+        );
+    }
+
+    #[test]
+    fn prefixes() {
+        let ip = IntegerParser::<u32>::new().radix_prefixes(true);
+        assert_eq!(ip.parse(&"0xfF".into()).unwrap().0, 255);
+        assert_eq!(ip.parse(&"0o17".into()).unwrap().0, 15);
+        assert_eq!(ip.parse(&"0b101".into()).unwrap().0, 5);
+        assert_eq!(ip.parse(&"10".into()).unwrap().0, 10);
+        // A prefix without digits is a 0, like any other trailing letters
+        let (p, rest) = ip.parse(&"0xg".into()).unwrap();
+        assert_eq!(p, 0);
+        assert_eq!(rest.source, "xg");
+        let (p, rest) = ip.parse(&"0x".into()).unwrap();
+        assert_eq!(p, 0);
+        assert_eq!(rest.source, "x");
+        assert!(ip
+            .parse(&Input::from("0x").partial(true))
+            .is_err_and(|e| e.is_incomplete()));
+        // The prefixes may be upper case, like the digits
+        assert_eq!(ip.parse(&"0XFF".into()).unwrap().0, 255);
+        assert_eq!(ip.parse(&"0O17".into()).unwrap().0, 15);
+        assert_eq!(ip.parse(&"0B101".into()).unwrap().0, 5);
+
+        let (p, rest) = ip.parse(&"0b102".into()).unwrap();
+        assert_eq!(p, 2);
+        assert_eq!(rest.source, "2");
+
+        // Without prefixes, only the leading 0 is parsed
+        let (p, rest) = integer::<u32>().parse(&"0xff".into()).unwrap();
+        assert_eq!(p, 0);
+        assert_eq!(rest.source, "xff");
+    }
+
+    #[test]
+    fn separators() {
+        let ip = integer::<i64>().separator('_');
+        assert_eq!(ip.parse(&"1_000_000".into()).unwrap().0, 1_000_000);
+
+        let (p, rest) = ip.parse(&"12_ rest".into()).unwrap();
+        assert_eq!(p, 12);
+        assert_eq!(rest.source, "_ rest");
+
+        let (p, rest) = ip.parse(&"1__2".into()).unwrap();
+        assert_eq!(p, 1);
+        assert_eq!(rest.source, "__2");
+
+        assert!(ip.parse(&"_1".into()).is_err());
+        assert_eq!(integer::<i64>().parse(&"1_000".into()).unwrap().0, 1);
+    }
+
+    #[test]
+    fn floats() {
+        let cases = [
+            ("0", 0.0),
+            ("-0", -0.0),
+            ("12", 12.0),
+            ("1.5", 1.5),
+            ("-0.25", -0.25),
+            ("1e3", 1000.0),
+            ("1E+3", 1000.0),
+            ("25e-2", 0.25),
+            ("-1.5e2", -150.0),
+        ];
+        for (source, expected) in cases {
+            let (p, rest) = float().parse(&source.into()).unwrap();
+            assert_eq!(p, expected, "{source}");
+            assert!(rest.source.is_empty(), "{source}");
+        }
+
+        let (p, rest) = float().parse(&"01".into()).unwrap();
+        assert_eq!(p, 0.0);
+        assert_eq!(rest.source, "1");
+
+        for source in ["", "-", "+1", ".5", "1.", "1.e3", "1e", "1e+", "\u{663}"] {
+            assert!(float().parse(&source.into()).is_err(), "{source}");
+        }
+
+        let (p, _) = FloatParser::<f32>::new().parse(&"0.5".into()).unwrap();
+        assert_eq!(p, 0.5f32);
+    }
+}
@@ -1,25 +1,16 @@
 use std::fmt::Debug;
 
 use crate::{
-    errors::{ParsingError, ParsingErrorKind},
-    traits::Parser,
+    describe::Description, errors::ParsingError, inputs::ParserInput, traits::Parser,
     type_alias::ParserRes,
 };
 
-pub struct OrThenParser<A, B>
-where
-    A: Parser,
-    B: Parser,
-{
+pub struct OrThenParser<A, B> {
     pub first_parse: A,
     pub second_parse: B,
 }
 
-impl<A, B> From<(A, B)> for OrThenParser<A, B>
-where
-    A: Parser,
-    B: Parser,
-{
+impl<A, B> From<(A, B)> for OrThenParser<A, B> {
     fn from((ap, bp): (A, B)) -> Self {
         Self {
             first_parse: ap,
@@ -28,21 +19,24 @@ where
     }
 }
 
-impl<A, B, CommonOut> Parser for OrThenParser<A, B>
+impl<I, A, B, CommonOut> Parser<I> for OrThenParser<A, B>
 where
-    A: Parser<Output = CommonOut>,
-    B: Parser<Output = CommonOut>,
+    I: ParserInput,
+    A: Parser<I, Output = CommonOut>,
+    B: Parser<I, Output = CommonOut>,
     CommonOut: Debug,
 {
     type Output = CommonOut;
-    fn parse(&self, input: &crate::inputs::Input) -> ParserRes<Self::Output> {
-        let aparse = self.first_parse.parse(&input);
-        if aparse.is_ok() {
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        // An option that needs more input could still succeed, so the other option
+        // should not be chosen yet
+        let aparse = self.first_parse.parse(input);
+        if aparse.as_ref().map_or_else(|e| e.is_incomplete(), |_| true) {
             return aparse;
         }
 
-        let bparse = self.second_parse.parse(&input);
-        if bparse.is_ok() {
+        let bparse = self.second_parse.parse(input);
+        if bparse.as_ref().map_or_else(|e| e.is_incomplete(), |_| true) {
             return bparse;
         };
 
@@ -52,6 +46,10 @@ where
         let berr = bparse.unwrap_err();
         Err(if berr > aerr { berr } else { aerr })
     }
+
+    fn describe(&self) -> Description {
+        Description::choice(self.first_parse.describe(), self.second_parse.describe())
+    }
 }
 
 #[cfg(test)]
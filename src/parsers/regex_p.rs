@@ -0,0 +1,311 @@
+use std::fmt::Debug;
+
+use regex::{Captures, Regex};
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Parse the text matched by a regular expression, starting at the current position of
+/// the input.
+///
+/// The pattern is always anchored, so it must match at the start of the input. The
+/// output is the matched text. To get the capture groups instead, see `captures`.
+///
+/// On a partial input, a match that reaches the end of the input could go on in the next
+/// part, so it is an `Incomplete` error, as is an empty partial input.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::regex_p::ParseRegex;
+/// use parlib::traits::Parser;
+///
+/// let date = ParseRegex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
+/// let (parsed, rest) = date.parse(&"2024-01-31 rest".into()).unwrap();
+/// assert_eq!(parsed, "2024-01-31".to_string());
+/// assert_eq!(rest.source, " rest".to_string());
+///
+/// // The pattern is anchored at the current position
+/// assert!(date.parse(&"on 2024-01-31".into()).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseRegex {
+    pattern: String,
+    regex: Regex,
+}
+
+impl ParseRegex {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(ParseRegex {
+            pattern: pattern.to_string(),
+            regex: Regex::new(&format!(r"\A(?:{pattern})"))?,
+        })
+    }
+
+    /// Output the capture groups of the match rather than the matched text
+    pub fn captures<T>(self) -> ParseCaptures<T>
+    where
+        T: FromCaptures,
+    {
+        ParseCaptures {
+            parser: self,
+            output: std::marker::PhantomData,
+        }
+    }
+
+    fn error(&self, input: &Input) -> ParsingError {
+        let kind = ParsingErrorKind::Expected(format!("text matching /{}/", self.pattern));
+        ParsingError::new(kind, input.line, input.col)
+    }
+
+    /// Whether a match (or no match) could change once more of a partial input is added
+    fn check_partial(&self, input: &Input, end: Option<usize>) -> Result<(), ParsingError> {
+        let at_end = match end {
+            Some(end) => end == input.source.len(),
+            None => input.source.is_empty(),
+        };
+        match input.is_partial() && at_end {
+            true => Err(input.incomplete(Needed::Unknown)),
+            false => Ok(()),
+        }
+    }
+
+    fn advance(&self, input: &Input, len: usize) -> Input {
+        input
+            .clone()
+            .char_offset(input.source[..len].chars().count())
+    }
+}
+
+impl Parser for ParseRegex {
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let found = self.regex.find(&input.source);
+        self.check_partial(input, found.map(|m| m.end()))?;
+        let found = found.ok_or_else(|| self.error(input))?;
+        Ok((found.as_str().to_string(), self.advance(input, found.end())))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class(format!("/{}/", self.pattern))
+    }
+}
+
+/// Build some value from the capture groups of a regex match
+///
+/// This is implemented for tuples of up to six `String`s (groups `1` to `6`), and for
+/// `Vec<Option<String>>` (every group, including the whole match as group `0`). Return
+/// `None` if the captures do not make a valid value.
+pub trait FromCaptures: Sized + Debug {
+    fn from_captures(captures: &Captures) -> Option<Self>;
+}
+
+impl FromCaptures for Vec<Option<String>> {
+    fn from_captures(captures: &Captures) -> Option<Self> {
+        Some(
+            captures
+                .iter()
+                .map(|group| group.map(|m| m.as_str().to_string()))
+                .collect(),
+        )
+    }
+}
+
+macro_rules! impl_from_captures {
+    ($(($($group:literal),+)),+) => {
+        $(
+            impl FromCaptures for ($(impl_from_captures!(@string $group),)+) {
+                fn from_captures(captures: &Captures) -> Option<Self> {
+                    Some(($(captures.get($group)?.as_str().to_string(),)+))
+                }
+            }
+        )+
+    };
+    (@string $group:literal) => { String };
+}
+
+impl_from_captures!(
+    (1),
+    (1, 2),
+    (1, 2, 3),
+    (1, 2, 3, 4),
+    (1, 2, 3, 4, 5),
+    (1, 2, 3, 4, 5, 6)
+);
+
+/// Parse the capture groups of a regular expression, see `ParseRegex::captures`
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::regex_p::ParseRegex;
+/// use parlib::traits::Parser;
+///
+/// let time = ParseRegex::new(r"(\d{2}):(\d{2})").unwrap().captures::<(String, String)>();
+/// let ((hours, minutes), _) = time.parse(&"13:45".into()).unwrap();
+/// assert_eq!(hours, "13".to_string());
+/// assert_eq!(minutes, "45".to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseCaptures<T>
+where
+    T: FromCaptures,
+{
+    parser: ParseRegex,
+    output: std::marker::PhantomData<T>,
+}
+
+impl<T> Parser for ParseCaptures<T>
+where
+    T: FromCaptures,
+{
+    type Output = T;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let captures = self.parser.regex.captures(&input.source);
+        let end = captures.as_ref().and_then(|c| c.get(0)).map(|m| m.end());
+        self.parser.check_partial(input, end)?;
+        let captures = captures.ok_or_else(|| self.parser.error(input))?;
+        let len = captures.get(0).map_or(0, |m| m.end());
+        let rest = self.parser.advance(input, len);
+
+        let Some(output) = T::from_captures(&captures) else {
+            let kind = ParsingErrorKind::MappingError(format!(
+                "the captures of /{}/ could not be converted",
+                self.parser.pattern
+            ));
+            return Err(ParsingError::new(kind, rest.line, rest.col));
+        };
+        Ok((output, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_regex {
+    use super::{Captures, FromCaptures, ParseRegex};
+    use crate::{errors::ParsingErrorKind, inputs::Input, traits::Parser};
+
+    #[test]
+    fn anchored_match() {
+        let uuid = ParseRegex::new(r"[0-9a-f]{8}-([0-9a-f]{4}-){3}[0-9a-f]{12}").unwrap();
+        let source = "123e4567-e89b-12d3-a456-426614174000,";
+        let (p, rest) = uuid.parse(&source.into()).unwrap();
+        assert_eq!(p, source.trim_end_matches(','));
+        assert_eq!(rest.source, ",");
+        assert_eq!(rest.col, 36);
+
+        let err = ParseRegex::new("a+")
+            .unwrap()
+            .parse(&"baa".into())
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("text matching /a+/".to_string())
+        );
+
+        // Alternations are anchored as a whole
+        let ab = ParseRegex::new("a|b").unwrap();
+        assert!(ab.parse(&"cb".into()).is_err());
+        assert!(ParseRegex::new("(").is_err());
+    }
+
+    #[test]
+    fn advances_lines() {
+        let lines = ParseRegex::new(r"(\w+\n)+").unwrap();
+        let (p, rest) = lines.parse(&"one\n\u{e9}t\u{e9}\nthree".into()).unwrap();
+        assert_eq!(p, "one\n\u{e9}t\u{e9}\n");
+        assert_eq!((rest.line, rest.col), (2, 0));
+        assert_eq!(rest.source, "three");
+
+        let (_, rest) = ParseRegex::new(r"\w+").unwrap().parse(&rest).unwrap();
+        assert_eq!((rest.line, rest.col), (2, 5));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Version {
+        major: u32,
+        minor: u32,
+    }
+
+    impl FromCaptures for Version {
+        fn from_captures(captures: &Captures) -> Option<Self> {
+            Some(Version {
+                major: captures.name("major")?.as_str().parse().ok()?,
+                minor: captures.name("minor")?.as_str().parse().ok()?,
+            })
+        }
+    }
+
+    #[test]
+    fn captures() {
+        let kv = ParseRegex::new(r"(\w+)=(\w*)")
+            .unwrap()
+            .captures::<(String, String)>();
+        let ((k, v), rest) = kv.parse(&"key=value;".into()).unwrap();
+        assert_eq!((k.as_str(), v.as_str()), ("key", "value"));
+        assert_eq!(rest.source, ";");
+
+        let all = ParseRegex::new(r"(a)(b)?")
+            .unwrap()
+            .captures::<Vec<Option<String>>>();
+        let (groups, _) = all.parse(&"ac".into()).unwrap();
+        assert_eq!(
+            groups,
+            vec![Some("a".to_string()), Some("a".to_string()), None]
+        );
+
+        let version = ParseRegex::new(r"(?<major>\d+)\.(?<minor>\d+)")
+            .unwrap()
+            .captures::<Version>();
+        let (v, _) = version.parse(&"1.12".into()).unwrap();
+        assert_eq!(
+            v,
+            Version {
+                major: 1,
+                minor: 12
+            }
+        );
+
+        // The regex matched, but the value could not be built
+        let err = version.parse(&"1.99999999999".into()).unwrap_err();
+        assert!(matches!(err.kind(), ParsingErrorKind::MappingError(_)));
+
+        let missing = ParseRegex::new(r"(a)|(b)")
+            .unwrap()
+            .captures::<(String, String)>();
+        assert!(missing.parse(&"a".into()).is_err());
+    }
+
+    #[test]
+    fn partial_input() {
+        let digits = ParseRegex::new(r"\d+").unwrap();
+        let partial = |source: &str| Input::from(source).partial(true);
+
+        // The number may go on in the next part of the input
+        assert!(digits
+            .parse(&partial("12"))
+            .is_err_and(|e| e.is_incomplete()));
+        assert!(digits.parse(&partial("")).is_err_and(|e| e.is_incomplete()));
+        let (p, _) = digits.parse(&partial("12;")).unwrap();
+        assert_eq!(p, "12");
+        assert!(digits
+            .parse(&partial("x"))
+            .is_err_and(|e| !e.is_incomplete()));
+        assert!(digits.parse(&"12".into()).is_ok());
+
+        let pair = ParseRegex::new(r"(\d+),(\d+)")
+            .unwrap()
+            .captures::<(String, String)>();
+        assert!(pair
+            .parse(&partial("1,2"))
+            .is_err_and(|e| e.is_incomplete()));
+    }
+}
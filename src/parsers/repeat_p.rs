@@ -1,20 +1,38 @@
+use std::{fmt::Debug, marker::PhantomData};
+
 use crate::{
+    describe::Description,
     errors::{ParsingError, ParsingErrorKind},
-    inputs::Input,
+    inputs::ParserInput,
     traits::Parser,
+    type_alias::ParserRes,
 };
 
 /// Run the same parser repeatedly
 ///
 /// Optionally, you can set a range. The minimum number of times the parser must
 /// be run, and the limit.
-pub struct RepeatParser<P>
-where
-    P: Parser,
-{
+///
+/// By default the outputs are collected into a `Vec`, but any collection that
+/// implements `Default` and `Extend` can be used instead (see `collecting`).
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::repeat_p::RepeatParser;
+/// use parlib::parsers::ParseIf;
+/// use parlib::traits::Parser;
+///
+/// let digits = RepeatParser::new(ParseIf(|c| c.is_ascii_digit())).collecting::<String>();
+/// let (parsed, rest) = digits.parse(&"123abc".into()).unwrap();
+/// assert_eq!(parsed, "123".to_string());
+/// assert_eq!(rest.source, "abc".to_string());
+/// ```
+pub struct RepeatParser<P, C = Vec<<P as Parser>::Output>> {
     parser: P,
     lower_bound: usize,
     upper_bound: Option<usize>,
+    collection: PhantomData<C>,
 }
 
 impl<P> RepeatParser<P>
@@ -23,13 +41,11 @@ where
 {
     /// By default, the parser must run *at least* 1 time, with no maximum
     pub fn new(p: P) -> Self {
-        Self {
-            parser: p,
-            lower_bound: 1,
-            upper_bound: None,
-        }
+        repeat(p)
     }
+}
 
+impl<P, C> RepeatParser<P, C> {
     pub fn minm(mut self, l: usize) -> Self {
         self.lower_bound = l;
         self
@@ -39,47 +55,281 @@ where
         self.upper_bound = Some(l);
         self
     }
+
+    /// Collect the outputs into some other collection, such as a `String` or a `HashMap`
+    pub fn collecting<NC>(self) -> RepeatParser<P, NC> {
+        RepeatParser {
+            parser: self.parser,
+            lower_bound: self.lower_bound,
+            upper_bound: self.upper_bound,
+            collection: PhantomData,
+        }
+    }
 }
 
-impl<P> Parser for RepeatParser<P>
+impl<I, P, C> Parser<I> for RepeatParser<P, C>
 where
-    P: Parser,
+    I: ParserInput,
+    P: Parser<I>,
+    C: Default + Extend<P::Output> + Debug,
 {
-    type Output = Vec<P::Output>;
-    fn parse(&self, input: &Input) -> crate::type_alias::ParserRes<Self::Output> {
+    type Output = C;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
         let mut rest = input.clone();
-        let mut acc = vec![];
+        let mut acc = C::default();
+        let mut count = 0;
         loop {
             if let Some(limit) = self.upper_bound {
-                if acc.len() >= limit {
+                if count >= limit {
                     break;
                 }
             }
 
-            let Ok((p, r)) = self.parser.parse(&rest) else {
-                break;
+            let (p, r) = match self.parser.parse(&rest) {
+                Ok(parsed) => parsed,
+                Err(e) if e.is_incomplete() => return Err(e),
+                Err(_) => break,
             };
 
+            // A parser that did not consume anything would succeed forever
+            let consumed = r.remaining() != rest.remaining();
+
             rest = r;
-            acc.push(p);
+            acc.extend(std::iter::once(p));
+            count += 1;
+
+            if !consumed {
+                break;
+            }
         }
 
-        if acc.len() < self.lower_bound {
+        if count < self.lower_bound {
             let err_kind = ParsingErrorKind::PatternNotFound(
                 "Parser did not run the minum number of times".to_string(),
             );
-            return Err(ParsingError::new(err_kind, rest.line, rest.col));
+            let at = rest.position();
+            return Err(ParsingError::new(err_kind, at.line, at.col));
         }
 
         Ok((acc, rest))
     }
+
+    fn describe(&self) -> Description {
+        Description::repeat(self.parser.describe(), self.lower_bound, self.upper_bound)
+    }
+}
+
+/// Run a parser repeatedly, at least once, collecting the outputs into a `Vec`.
+///
+/// This is the same as `RepeatParser::new`, but it works with parsers over any input, such
+/// as a stream of tokens.
+pub fn repeat<I, P>(p: P) -> RepeatParser<P, Vec<P::Output>>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    RepeatParser {
+        parser: p,
+        lower_bound: 1,
+        upper_bound: None,
+        collection: PhantomData,
+    }
+}
+
+/// A collection that throws away everything that is added to it.
+///
+/// This is used by `skip_many` so that repeating a parser does not allocate.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Discard;
+
+impl<A> Extend<A> for Discard {
+    fn extend<T: IntoIterator<Item = A>>(&mut self, iter: T) {
+        iter.into_iter().for_each(drop);
+    }
+}
+
+/// Run a parser exactly `n` times
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::repeat_p::count;
+/// use parlib::parsers::ParseIf;
+/// use parlib::traits::Parser;
+///
+/// let (parsed, rest) = count(2, ParseIf(|c| c.is_alphabetic())).parse(&"abc".into()).unwrap();
+/// assert_eq!(parsed, vec!['a', 'b']);
+/// assert_eq!(rest.source, "c".to_string());
+///
+/// assert!(count(4, ParseIf(|c| c.is_alphabetic())).parse(&"abc".into()).is_err());
+/// ```
+pub fn count<I, P>(n: usize, p: P) -> RepeatParser<P, Vec<P::Output>>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    repeat(p).minm(n).maxm(n)
+}
+
+/// Run a parser zero or more times, discarding the outputs
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::repeat_p::skip_many;
+/// use parlib::parsers::ParseMatch;
+/// use parlib::traits::Parser;
+///
+/// let (_, rest) = skip_many(ParseMatch(" ")).parse(&"   hello".into()).unwrap();
+/// assert_eq!(rest.source, "hello".to_string());
+/// ```
+pub fn skip_many<I, P>(p: P) -> RepeatParser<P, Discard>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    repeat(p).minm(0).collecting()
+}
+
+/// Run some parser repeatedly until another parser (`end`) succeeds
+///
+/// The output of `end` is discarded. If the repeated parser fails before `end`
+/// is found, its error is returned.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::repeat_p::many_till;
+/// use parlib::parsers::{ParseIf, ParseMatch};
+/// use parlib::traits::Parser;
+///
+/// let comment = many_till(ParseIf(|_| true), ParseMatch("*/")).collecting::<String>();
+/// let (parsed, rest) = comment.parse(&" a comment */ code".into()).unwrap();
+/// assert_eq!(parsed, " a comment ".to_string());
+/// assert_eq!(rest.source, " code".to_string());
+/// ```
+pub struct ManyTillParser<P, E, C = Vec<<P as Parser>::Output>> {
+    parser: P,
+    end: E,
+    collection: PhantomData<C>,
+}
+
+impl<P, E, C> ManyTillParser<P, E, C> {
+    /// Collect the outputs into some other collection, such as a `String` or a `HashMap`
+    pub fn collecting<NC>(self) -> ManyTillParser<P, E, NC> {
+        ManyTillParser {
+            parser: self.parser,
+            end: self.end,
+            collection: PhantomData,
+        }
+    }
+}
+
+impl<I, P, E, C> Parser<I> for ManyTillParser<P, E, C>
+where
+    I: ParserInput,
+    P: Parser<I>,
+    E: Parser<I>,
+    C: Default + Extend<P::Output> + Debug,
+{
+    type Output = C;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        let mut rest = input.clone();
+        let mut acc = C::default();
+        loop {
+            match self.end.parse(&rest) {
+                Ok((_, r)) => return Ok((acc, r)),
+                Err(e) if e.is_incomplete() => return Err(e),
+                Err(_) => {}
+            }
+
+            let (p, r) = self.parser.parse(&rest)?;
+            if r.remaining() == rest.remaining() {
+                let kind = ParsingErrorKind::PatternNotFound(
+                    "Parser made no progress before the end was found".to_string(),
+                );
+                let at = rest.position();
+                return Err(ParsingError::new(kind, at.line, at.col));
+            }
+
+            rest = r;
+            acc.extend(std::iter::once(p));
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::sequence(
+            Description::repeat(self.parser.describe(), 0, None),
+            self.end.describe(),
+        )
+    }
+}
+
+pub fn many_till<I, P, E>(p: P, end: E) -> ManyTillParser<P, E, Vec<P::Output>>
+where
+    I: ParserInput,
+    P: Parser<I>,
+    E: Parser<I>,
+{
+    ManyTillParser {
+        parser: p,
+        end,
+        collection: PhantomData,
+    }
+}
+
+/// Run a parser at most once, see `optional`
+#[derive(Debug, Clone, Copy)]
+pub struct Optional<P> {
+    parser: P,
+}
+
+impl<I, P> Parser<I> for Optional<P>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    type Output = Option<P::Output>;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        match self.parser.parse(input) {
+            Ok((parsed, rest)) => Ok((Some(parsed), rest)),
+            Err(e) if e.is_incomplete() => Err(e),
+            Err(_) => Ok((None, input.clone())),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::repeat(self.parser.describe(), 0, Some(1))
+    }
+}
+
+/// Run a parser if it can, outputting `None` without consuming anything if it fails
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::repeat_p::optional;
+/// use parlib::parsers::ParseMatch;
+/// use parlib::traits::Parser;
+///
+/// let sign = optional(ParseMatch("-"));
+/// assert_eq!(sign.parse(&"-1".into()).unwrap().0, Some("-".to_string()));
+/// assert_eq!(sign.parse(&"1".into()).unwrap().0, None);
+/// ```
+pub fn optional<I, P>(p: P) -> Optional<P>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    Optional { parser: p }
 }
 
 #[cfg(test)]
 mod parse_many_t {
+    use std::collections::HashMap;
 
-    use crate::parsers::and_p::KeepSecondOutputOnly;
-    use crate::parsers::{ParseWhile, ParseWhileOrNothing};
+    use crate::parsers::and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly};
+    use crate::parsers::{ParseIf, ParseMatch, ParseWhile, ParseWhileOrNothing};
     use crate::traits::Parser;
 
     #[test]
@@ -117,4 +367,85 @@ mod parse_many_t {
         assert_eq!(acc, exp);
         assert_eq!(rest.source, " this is a text".to_string());
     }
+
+    #[test]
+    fn repeat_into_other_collections() {
+        let pair = ParseIf(|c| c.is_alphabetic())
+            .and_then(ParseIf(|c| c.is_numeric()))
+            .and_then(ParseWhileOrNothing(|c| c == ','))
+            .combine(KeepFirstOutputOnly);
+        let map_p = super::RepeatParser::new(pair).collecting::<HashMap<char, char>>();
+        let (map, rest) = map_p.parse(&"a1,b2,c3".into()).unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&'b'), Some(&'2'));
+        assert!(rest.source.is_empty());
+
+        let word_p = super::RepeatParser::new(ParseIf(|c| c.is_alphabetic()))
+            .maxm(3)
+            .collecting::<String>();
+        let (word, rest) = word_p.parse(&"hello".into()).unwrap();
+        assert_eq!(word, "hel".to_string());
+        assert_eq!(rest.source, "lo".to_string());
+    }
+
+    #[test]
+    fn repeat_stops_without_progress() {
+        let many_ws = super::RepeatParser::new(ParseWhileOrNothing(|c| c.is_whitespace()));
+        let (acc, rest) = many_ws.parse(&"abc".into()).unwrap();
+        assert_eq!(acc, vec!["".to_string()]);
+        assert_eq!(rest.source, "abc".to_string());
+    }
+
+    #[test]
+    fn repeat_stops_after_empty_parse() {
+        // The parse that consumed nothing is kept, and the repetition ends there
+        let many_x = super::RepeatParser::new(super::optional(ParseMatch("x")));
+        let (acc, rest) = many_x.parse(&"xx1".into()).unwrap();
+        assert_eq!(
+            acc,
+            vec![Some("x".to_string()), Some("x".to_string()), None]
+        );
+        assert_eq!(rest.source, "1".to_string());
+        assert_eq!(rest.col, 2);
+    }
+
+    #[test]
+    fn count_exact() {
+        let digits = super::count(3, ParseIf(|c| c.is_numeric()));
+        let (acc, rest) = digits.parse(&"12345".into()).unwrap();
+        assert_eq!(acc, vec!['1', '2', '3']);
+        assert_eq!(rest.source, "45".to_string());
+
+        assert!(digits.parse(&"12a".into()).is_err());
+    }
+
+    #[test]
+    fn skip_many_discards() {
+        let (_, rest) = super::skip_many(ParseMatch("ab"))
+            .parse(&"ababac".into())
+            .unwrap();
+        assert_eq!(rest.source, "ac".to_string());
+        assert_eq!(rest.col, 4);
+
+        let (_, rest) = super::skip_many(ParseMatch("ab"))
+            .parse(&"xyz".into())
+            .unwrap();
+        assert_eq!(rest.source, "xyz".to_string());
+    }
+
+    #[test]
+    fn many_till_end() {
+        let till = super::many_till(ParseIf(|c| c.is_alphabetic()), ParseMatch(";"));
+        let (acc, rest) = till.parse(&"abc; rest".into()).unwrap();
+        assert_eq!(acc, vec!['a', 'b', 'c']);
+        assert_eq!(rest.source, " rest".to_string());
+
+        let (acc, _) = till.parse(&";".into()).unwrap();
+        assert!(acc.is_empty());
+
+        // The repeated parser fails before the end is found
+        assert!(till.parse(&"ab1;".into()).is_err());
+        // The end is never found
+        assert!(till.parse(&"abc".into()).is_err());
+    }
 }
@@ -0,0 +1,285 @@
+use std::fmt::Debug;
+
+use crate::{
+    describe::Description,
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::{Input, ParserInput, Span},
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// Some value, together with the part of the source that it was parsed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Spanned { node, span }
+    }
+
+    /// Map the value, keeping the same span
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            node: f(self.node),
+            span: self.span,
+        }
+    }
+
+    pub fn as_ref(&self) -> Spanned<&T> {
+        Spanned {
+            node: &self.node,
+            span: self.span,
+        }
+    }
+}
+
+/// Run some parser, and output the text that it consumed rather than its output
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::ParseWhile;
+/// use parlib::traits::Parser;
+///
+/// let assignment = ParseWhile(|c| c.is_alphabetic())
+///     .and_then(ParseWhile(|c| c == ' ' || c == '='))
+///     .and_then(ParseWhile(|c| c.is_numeric()))
+///     .recognize();
+/// let (parsed, rest) = assignment.parse(&"x = 12;".into()).unwrap();
+/// assert_eq!(parsed, "x = 12".to_string());
+/// assert_eq!(rest.source, ";".to_string());
+/// ```
+pub struct Recognize<P>
+where
+    P: Parser,
+{
+    parser: P,
+}
+
+impl<P> Recognize<P>
+where
+    P: Parser,
+{
+    pub fn new(parser: P) -> Self {
+        Recognize { parser }
+    }
+}
+
+impl<P> Parser for Recognize<P>
+where
+    P: Parser,
+{
+    type Output = String;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (_, rest) = self.parser.parse(input)?;
+        let consumed = consumed_source(input, &rest)?;
+        Ok((consumed, rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+/// The source between `input` and the `rest` that a parser returned for it. A parser that
+/// returns an input from before where it started, or from some other source, is an error
+/// rather than a panic.
+pub(crate) fn consumed_source(input: &Input, rest: &Input) -> Result<String, ParsingError> {
+    input.consumed(rest).ok_or_else(|| {
+        let kind = ParsingErrorKind::PatternNotFound(
+            "the parser did not return a part of the input that it was given".to_string(),
+        );
+        ParsingError::new(kind, input.line, input.col)
+    })
+}
+
+/// Run some parser, and output the span of the source that it consumed along with its
+/// output
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::ParseMatch;
+/// use parlib::traits::Parser;
+///
+/// let input = "\n  let".into();
+/// let (parsed, _) = ParseMatch("\n  ").and_then(ParseMatch("let").with_span()).parse(&input).unwrap();
+/// let (_, (word, span)) = parsed;
+/// assert_eq!(word, "let".to_string());
+/// assert_eq!((span.start.line, span.start.col, span.start.offset), (1, 2, 3));
+/// assert_eq!((span.end.line, span.end.col, span.end.offset), (1, 5, 6));
+/// ```
+pub struct WithSpan<P>
+where
+    P: Parser,
+{
+    parser: P,
+}
+
+impl<P> WithSpan<P>
+where
+    P: Parser,
+{
+    pub fn new(parser: P) -> Self {
+        WithSpan { parser }
+    }
+}
+
+impl<P> Parser for WithSpan<P>
+where
+    P: Parser,
+{
+    type Output = (P::Output, Span);
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        Ok(((parsed, Span::new(input.position(), rest.position())), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+/// Run some parser, and wrap its output in a `Spanned`
+pub struct SpannedParser<P>
+where
+    P: Parser,
+{
+    parser: P,
+}
+
+impl<P> SpannedParser<P>
+where
+    P: Parser,
+{
+    pub fn new(parser: P) -> Self {
+        SpannedParser { parser }
+    }
+}
+
+impl<P> Parser for SpannedParser<P>
+where
+    P: Parser,
+{
+    type Output = Spanned<P::Output>;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        let span = Span::new(input.position(), rest.position());
+        Ok((Spanned::new(parsed, span), rest))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_span {
+    use crate::{
+        inputs::{Input, Position, Span},
+        parsers::{
+            and_p::KeepSecondOutputOnly, lexeme_p::identifier, repeat_p::RepeatParser,
+            string_p::StringParser, ParseMatch, ParseWhileOrNothing,
+        },
+        traits::Parser,
+        type_alias::ParserRes,
+    };
+
+    /// A broken parser, which goes back to the start of the source
+    struct Rewind;
+
+    impl Parser for Rewind {
+        type Output = ();
+        fn parse(&self, _: &Input) -> ParserRes<Self::Output> {
+            Ok(((), Input::from("abc")))
+        }
+    }
+
+    #[test]
+    fn recognize_composite() {
+        // The decoded string is "a\"b", but the source text keeps the escape
+        let sp = StringParser::new().recognize();
+        let (p, rest) = sp.parse(&r#""a\"b" rest"#.into()).unwrap();
+        assert_eq!(p, r#""a\"b""#);
+        assert_eq!(rest.source, " rest");
+
+        let words = RepeatParser::new(identifier().lexeme()).recognize();
+        let (p, _) = words.parse(&"\u{e9}t\u{e9} and more!".into()).unwrap();
+        assert_eq!(p, "\u{e9}t\u{e9} and more");
+    }
+
+    #[test]
+    fn spans_over_lines() {
+        let word = ParseWhileOrNothing(|c| c.is_whitespace())
+            .and_then(identifier().with_span())
+            .combine(KeepSecondOutputOnly);
+        let (parsed, rest) = RepeatParser::new(word)
+            .parse(&"ab\n \u{e9}f".into())
+            .unwrap();
+        assert!(rest.source.is_empty());
+
+        let spans = parsed.into_iter().map(|(_, span)| span).collect::<Vec<_>>();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(
+                    Position {
+                        line: 0,
+                        col: 0,
+                        offset: 0
+                    },
+                    Position {
+                        line: 0,
+                        col: 2,
+                        offset: 2
+                    }
+                ),
+                Span::new(
+                    Position {
+                        line: 1,
+                        col: 1,
+                        offset: 4
+                    },
+                    Position {
+                        line: 1,
+                        col: 3,
+                        offset: 7
+                    }
+                ),
+            ]
+        );
+        assert_eq!(spans[1].len(), 3);
+        assert_eq!(
+            spans[0].join(spans[1]),
+            Span::new(spans[0].start, spans[1].end)
+        );
+    }
+
+    #[test]
+    fn recognize_checks_the_rest() {
+        let input = Input::from("abc").char_offset(2);
+        let err = Rewind.recognize().parse(&input).unwrap_err();
+        assert_eq!(err.col(), 2);
+        assert!(!err.is_incomplete());
+    }
+
+    #[test]
+    fn spanned_nodes() {
+        let (parsed, _) = ParseMatch("a")
+            .and_then(ParseMatch("bc").spanned())
+            .parse(&"abc".into())
+            .unwrap();
+        let (_, spanned) = parsed;
+        assert_eq!(spanned.node, "bc");
+        assert_eq!((spanned.span.start.col, spanned.span.end.col), (1, 3));
+
+        let span = spanned.span;
+        let mapped = spanned.map(|s| s.len());
+        assert_eq!(mapped.node, 2);
+        assert_eq!(mapped.span, span);
+        assert!(!mapped.span.is_empty());
+    }
+}
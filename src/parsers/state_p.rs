@@ -0,0 +1,258 @@
+use std::{any::type_name, fmt::Debug, marker::PhantomData};
+
+use crate::{
+    describe::Description,
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+fn current_state<S>(input: &Input) -> Result<&S, ParsingError>
+where
+    S: 'static,
+{
+    input.state::<S>().ok_or_else(|| {
+        let kind = ParsingErrorKind::MissingState(type_name::<S>().to_string());
+        ParsingError::new(kind, input.line, input.col)
+    })
+}
+
+/// Output a copy of the user state, without consuming any input
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::inputs::Input;
+/// use parlib::parsers::state_p::{get_state, update_state};
+/// use parlib::traits::Parser;
+///
+/// let input = Input::from("").with_state(1u32);
+/// let (count, _) = update_state(|n: &u32| n + 1)
+///     .and_then(get_state::<u32>())
+///     .parse(&input)
+///     .unwrap();
+/// assert_eq!(count, ((), 2));
+/// ```
+pub struct GetState<S> {
+    state: PhantomData<S>,
+}
+
+impl<S> Parser for GetState<S>
+where
+    S: Clone + Debug + 'static,
+{
+    type Output = S;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        Ok((current_state::<S>(input)?.clone(), input.clone()))
+    }
+}
+
+pub fn get_state<S>() -> GetState<S> {
+    GetState { state: PhantomData }
+}
+
+/// Replace the user state, without consuming any input
+pub struct SetState<S> {
+    state: S,
+}
+
+impl<S> Parser for SetState<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Output = ();
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        Ok(((), input.clone().with_state(self.state.clone())))
+    }
+}
+
+pub fn set_state<S>(state: S) -> SetState<S> {
+    SetState { state }
+}
+
+/// Compute a new user state from the current one, without consuming any input
+pub struct UpdateState<S, F>
+where
+    F: Fn(&S) -> S,
+{
+    update: F,
+    state: PhantomData<S>,
+}
+
+impl<S, F> Parser for UpdateState<S, F>
+where
+    S: Send + Sync + 'static,
+    F: Fn(&S) -> S,
+{
+    type Output = ();
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let state = (self.update)(current_state::<S>(input)?);
+        Ok(((), input.clone().with_state(state)))
+    }
+}
+
+pub fn update_state<S, F>(update: F) -> UpdateState<S, F>
+where
+    F: Fn(&S) -> S,
+{
+    UpdateState {
+        update,
+        state: PhantomData,
+    }
+}
+
+/// Run some parser, and then compute a new user state from the current one and the
+/// output of the parser, see `Parser::and_update_state`
+pub struct AndUpdateState<P, S, F>
+where
+    P: Parser,
+    F: Fn(&S, &P::Output) -> S,
+{
+    parser: P,
+    update: F,
+    state: PhantomData<S>,
+}
+
+impl<P, S, F> AndUpdateState<P, S, F>
+where
+    P: Parser,
+    F: Fn(&S, &P::Output) -> S,
+{
+    pub fn new(parser: P, update: F) -> Self {
+        AndUpdateState {
+            parser,
+            update,
+            state: PhantomData,
+        }
+    }
+}
+
+impl<P, S, F> Parser for AndUpdateState<P, S, F>
+where
+    P: Parser,
+    S: Send + Sync + 'static,
+    F: Fn(&S, &P::Output) -> S,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (parsed, rest) = self.parser.parse(input)?;
+        let state = (self.update)(current_state::<S>(&rest)?, &parsed);
+        Ok((parsed, rest.with_state(state)))
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_state {
+    use std::collections::HashSet;
+
+    use super::{get_state, set_state, update_state};
+    use crate::{
+        errors::ParsingErrorKind,
+        inputs::Input,
+        parsers::{
+            and_p::{KeepFirstOutputOnly, KeepSecondOutputOnly},
+            lexeme_p::{identifier, keyword},
+            repeat_p::RepeatParser,
+            ParseMatch,
+        },
+        traits::Parser,
+    };
+
+    #[test]
+    fn get_and_set() {
+        let input = Input::from("abc").with_state("start".to_string());
+        let (p, rest) = get_state::<String>().parse(&input).unwrap();
+        assert_eq!(p, "start");
+        assert_eq!(rest.source, "abc");
+
+        let (_, rest) = ParseMatch("a")
+            .and_then(set_state("after a".to_string()))
+            .parse(&input)
+            .unwrap();
+        assert_eq!(rest.state::<String>().unwrap(), "after a");
+        assert_eq!(rest.source, "bc");
+
+        // The original input is not changed
+        assert_eq!(input.state::<String>().unwrap(), "start");
+    }
+
+    #[test]
+    fn missing_state() {
+        let err = get_state::<u8>().parse(&"abc".into()).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::MissingState("u8".to_string())
+        );
+
+        // The wrong type of state is the same as no state
+        let input = Input::from("").with_state(1u32);
+        assert!(update_state(|n: &u8| n + 1).parse(&input).is_err());
+    }
+
+    #[test]
+    fn rolled_back_when_backtracking() {
+        let counted = |s| {
+            ParseMatch(s)
+                .and_then(update_state(|n: &u32| n + 1))
+                .combine(KeepFirstOutputOnly)
+        };
+        // The first branch counts a, but then fails on c
+        let p = counted("a")
+            .and_then(ParseMatch("c"))
+            .combine(KeepFirstOutputOnly)
+            .otherwise(
+                counted("a")
+                    .and_then(counted("b"))
+                    .combine(KeepSecondOutputOnly),
+            );
+
+        let input = Input::from("ab").with_state(0u32);
+        let (_, rest) = p.parse(&input).unwrap();
+        assert_eq!(rest.state::<u32>(), Some(&2));
+    }
+
+    #[test]
+    fn typedef_names() {
+        // `typedef name;` adds name to the set of types, which can then be used in
+        // declarations such as `name x;`
+        let typedef = keyword("typedef")
+            .lexeme()
+            .and_then(
+                identifier().and_update_state(|types: &HashSet<String>, name: &String| {
+                    let mut types = types.clone();
+                    types.insert(name.clone());
+                    types
+                }),
+            )
+            .combine(KeepSecondOutputOnly)
+            .with_mapping(&|name| format!("typedef {name}"));
+        let declaration = identifier()
+            .lexeme()
+            .and_then(get_state::<HashSet<String>>())
+            .with_try_mapping(&|(ty, types)| types.contains(&ty).then_some(ty))
+            .and_then(identifier())
+            .with_mapping(&|(ty, name)| format!("{name}: {ty}"));
+        let statements = RepeatParser::new(
+            typedef
+                .otherwise(declaration)
+                .and_then(ParseMatch(";").lexeme())
+                .combine(KeepFirstOutputOnly),
+        );
+
+        let input = Input::from("typedef num; num x; typedef u8; u8 y;")
+            .with_state(HashSet::<String>::new());
+        let (p, rest) = statements.parse(&input).unwrap();
+        assert_eq!(p, vec!["typedef num", "x: num", "typedef u8", "y: u8"]);
+        assert_eq!(rest.state::<HashSet<String>>().unwrap().len(), 2);
+
+        let input = Input::from("typedef num; int x;").with_state(HashSet::<String>::new());
+        let (p, rest) = statements.parse(&input).unwrap();
+        assert_eq!(p.len(), 1);
+        assert_eq!(rest.source, "int x;");
+    }
+}
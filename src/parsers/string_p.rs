@@ -1,56 +1,417 @@
-use crate::{errors::ParsingError, inputs::Input, traits::Parser};
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+};
 
-use super::{ParseMatch, ParseWhile};
+/// Parse a string literal, decoding any escape sequences inside of it.
+///
+/// By default, the string must be surrounded by double quotes. This can be configured
+/// to parse many other kinds of string literals:
+///
+/// - Any quote character, such as `'` or a backtick (`quote`)
+/// - Rust style raw strings, such as `r#"no \escapes"#` (`raw`)
+/// - Python/TOML style multi-line strings, such as `"""..."""` (`triple_quoted`)
+/// - Strings that may not span multiple lines (`allow_newlines`)
+///
+/// The following escape sequences are always understood:
+///
+/// - `\"`, `\'`, `\\`, `\/`, and the configured quote character
+/// - `\n`, `\r`, `\t`, `\b` (backspace), `\f` (form feed) and `\0`
+///
+/// The following ones are enabled by default, and can each be turned off:
+///
+/// - `\uXXXX`, including UTF-16 surrogate pairs such as `\uD83D\uDE00` (`unicode`)
+/// - `\u{X}` to `\u{XXXXXX}` (`braced_unicode`)
+/// - `\xHH` (`hex`)
+/// - `\N`, `\NN` and `\NNN` octal escapes, up to `\377` (`octal`)
+///
+/// Any other escape sequence will give an `InvalidEscapeSequence` error at the position
+/// of the backslash.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::string_p::StringParser;
+/// use parlib::traits::Parser;
+///
+/// let sp = StringParser::new();
+/// let (parsed, _) = sp.parse(&r#""tab\tquote\" smile\uD83D\uDE00""#.into()).unwrap();
+/// assert_eq!(parsed, "tab\tquote\" smile\u{1F600}".to_string());
+///
+/// let sp = StringParser::new().hex(false);
+/// assert!(sp.parse(&r#""\x41""#.into()).is_err());
+///
+/// let sp = StringParser::new().quote('\'').raw();
+/// let (parsed, _) = sp.parse(&"r#'C:\\path'#".into()).unwrap();
+/// assert_eq!(parsed, "C:\\path".to_string());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct StringParser {
+    quote: char,
+    raw: bool,
+    triple_quoted: bool,
+    trim_first_newline: bool,
+    newlines: bool,
+    unicode: bool,
+    braced_unicode: bool,
+    hex: bool,
+    octal: bool,
+}
+
+impl Default for StringParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringParser {
+    /// By default, the string is surrounded by double quotes, it may contain new lines,
+    /// and all of the escape sequences are enabled
+    pub fn new() -> Self {
+        StringParser {
+            quote: '"',
+            raw: false,
+            triple_quoted: false,
+            trim_first_newline: false,
+            newlines: true,
+            unicode: true,
+            braced_unicode: true,
+            hex: true,
+            octal: true,
+        }
+    }
+
+    /// The character that opens and closes the string
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Parse a raw string, such as `r"..."` or `r##"..."##`. Escape sequences are not
+    /// decoded, and the string is closed by a quote followed by as many `#` as it was
+    /// opened with.
+    pub fn raw(mut self) -> Self {
+        self.raw = true;
+        self
+    }
+
+    /// Open and close the string with three quote characters, such as `"""..."""`
+    ///
+    /// As in TOML, the string may end with one or two quotes just before its closing
+    /// quotes, so `"""say "hi""""` is `say "hi"`.
+    pub fn triple_quoted(mut self) -> Self {
+        self.triple_quoted = true;
+        self
+    }
+
+    /// Drop a new line that immediately follows the opening quotes of a triple quoted
+    /// string, as TOML does
+    pub fn trim_first_newline(mut self, trim: bool) -> Self {
+        self.trim_first_newline = trim;
+        self
+    }
+
+    /// Allow the string to contain new lines. If this is not allowed, a new line before
+    /// the closing quote gives an error.
+    pub fn allow_newlines(mut self, allow: bool) -> Self {
+        self.newlines = allow;
+        self
+    }
+
+    /// Allow `\uXXXX` escapes (and surrogate pairs)
+    pub fn unicode(mut self, allow: bool) -> Self {
+        self.unicode = allow;
+        self
+    }
+
+    /// Allow `\u{...}` escapes
+    pub fn braced_unicode(mut self, allow: bool) -> Self {
+        self.braced_unicode = allow;
+        self
+    }
+
+    /// Allow `\xHH` escapes
+    pub fn hex(mut self, allow: bool) -> Self {
+        self.hex = allow;
+        self
+    }
+
+    /// Allow octal escapes such as `\101`
+    pub fn octal(mut self, allow: bool) -> Self {
+        self.octal = allow;
+        self
+    }
+
+    /// Decode the escape sequence that follows a backslash. The backslash must have
+    /// already been consumed.
+    fn escape(&self, chars: &mut Cursor) -> Result<char, String> {
+        let Some(c) = chars.next() else {
+            return Err("expected an escape sequence, found the end of the input".to_string());
+        };
+
+        let decoded = match c {
+            c if c == self.quote => c,
+            '"' => '"',
+            '\'' => '\'',
+            '\\' => '\\',
+            '/' => '/',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '0'..='7' if self.octal => return self.octal_escape(c, chars),
+            '0' => '\0',
+            'x' if self.hex => {
+                let code = chars.hex_digits(2)?;
+                char::from_u32(code).unwrap_or_default()
+            }
+            'u' if self.braced_unicode && chars.peek() == Some('{') => {
+                return self.braced_unicode_escape(chars)
+            }
+            'u' if self.unicode => return self.unicode_escape(chars),
+            other => return Err(format!("unknown escape sequence \\{other}")),
+        };
+        Ok(decoded)
+    }
 
-pub struct StringParser;
+    fn octal_escape(&self, first: char, chars: &mut Cursor) -> Result<char, String> {
+        let mut code = first.to_digit(8).unwrap_or_default();
+        for _ in 0..2 {
+            match chars.peek().and_then(|c| c.to_digit(8)) {
+                Some(d) => {
+                    chars.next();
+                    code = code * 8 + d;
+                }
+                None => break,
+            }
+        }
+        if code > 0o377 {
+            return Err(format!("octal escape \\{code:o} is out of range"));
+        }
+        Ok(char::from_u32(code).unwrap_or_default())
+    }
+
+    fn braced_unicode_escape(&self, chars: &mut Cursor) -> Result<char, String> {
+        // Skip the opening brace
+        chars.next();
+        let mut code: u32 = 0;
+        let mut digits = 0;
+        loop {
+            match chars.next() {
+                Some('}') if digits > 0 => break,
+                Some(c) if c.is_ascii_hexdigit() && digits < 6 => {
+                    code = code * 16 + c.to_digit(16).unwrap_or_default();
+                    digits += 1;
+                }
+                _ => return Err("expected 1 to 6 hex digits inside \\u{...}".to_string()),
+            }
+        }
+        char::from_u32(code).ok_or_else(|| format!("\\u{{{code:x}}} is not a unicode scalar value"))
+    }
+
+    fn unicode_escape(&self, chars: &mut Cursor) -> Result<char, String> {
+        let high = chars.hex_digits(4)?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high)
+                .ok_or_else(|| format!("\\u{high:04X} is a lone low surrogate"));
+        }
+
+        // A high surrogate must be followed by a low surrogate
+        if chars.next() != Some('\\') || chars.next() != Some('u') {
+            return Err(format!("\\u{high:04X} must be followed by a low surrogate"));
+        }
+        let low = chars.hex_digits(4)?;
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(format!("\\u{low:04X} is not a low surrogate"));
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        char::from_u32(code).ok_or_else(|| format!("{code:x} is not a unicode scalar value"))
+    }
+}
+
+/// Walks over the characters of a string literal, keeping track of how many have
+/// been consumed
+struct Cursor<'a> {
+    rest: &'a str,
+    consumed: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(source: &'a str) -> Self {
+        Cursor {
+            rest: source,
+            consumed: 0,
+        }
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.rest.chars().next()?;
+        self.rest = &self.rest[c.len_utf8()..];
+        self.consumed += 1;
+        Some(c)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest.chars().next()
+    }
+
+    /// Consume `pattern` if the rest of the input starts with it
+    fn eat(&mut self, pattern: &str) -> bool {
+        if !self.rest.starts_with(pattern) {
+            return false;
+        }
+        self.rest = &self.rest[pattern.len()..];
+        self.consumed += pattern.chars().count();
+        true
+    }
+
+    /// Read exactly `count` hex digits
+    fn hex_digits(&mut self, count: usize) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..count {
+            match self.next().and_then(|c| c.to_digit(16)) {
+                Some(d) => code = code * 16 + d,
+                None => return Err(format!("expected {count} hex digits")),
+            }
+        }
+        Ok(code)
+    }
+}
 
-// FIXME: Many things are broken here...
-// + Error will report the wrong line and column
 impl Parser for StringParser {
     type Output = String;
     fn parse(&self, input: &Input) -> crate::type_alias::ParserRes<Self::Output> {
-        // First, we will make sure that the first character is "
-        let (_, rest) = ParseMatch('"').parse(input)?;
+        let error_at = |kind, offset| {
+            let at = input.clone().char_offset(offset);
+            ParsingError::new(kind, at.line, at.col)
+        };
+
+        let mut chars = Cursor::new(&input.source);
+        // The rest of the string may be in the next part of a partial input
+        let incomplete = || input.incomplete(Needed::Unknown);
+
+        // Raw strings start with r, followed by any number of #
+        let mut hashes = 0;
+        if self.raw {
+            if !chars.eat("r") {
+                if input.is_partial() && input.source.is_empty() {
+                    return Err(incomplete());
+                }
+                let kind = ParsingErrorKind::PatternNotFound(
+                    "expected a raw string starting with r".to_string(),
+                );
+                return Err(error_at(kind, 0));
+            }
+            while chars.eat("#") {
+                hashes += 1;
+            }
+        }
+
+        let quotes = if self.triple_quoted { 3 } else { 1 };
+        let opening = self.quote.to_string().repeat(quotes);
+        let closing = format!("{opening}{}", "#".repeat(hashes));
+        if !chars.eat(&opening) {
+            if input.is_partial() && opening.starts_with(chars.rest) {
+                return Err(incomplete());
+            }
+            let kind =
+                ParsingErrorKind::PatternNotFound(format!("expected opening quote {opening}"));
+            return Err(error_at(kind, chars.consumed));
+        }
 
-        let mut acc = 1;
-        let mut chars = rest.source.chars().peekable();
+        if self.triple_quoted && self.trim_first_newline && !chars.eat("\n") {
+            chars.eat("\r\n");
+        }
+
+        let mut decoded = String::new();
         loop {
+            // Up to two quotes may come just before the closing quotes of a triple quoted
+            // string, so `"""a""""` is `a"`
+            let mut extra_quotes = 0;
+            while self.triple_quoted
+                && extra_quotes < 2
+                && chars
+                    .rest
+                    .strip_prefix(self.quote)
+                    .is_some_and(|r| r.starts_with(&closing))
+            {
+                chars.next();
+                decoded.push(self.quote);
+                extra_quotes += 1;
+            }
+            if chars.eat(&closing) {
+                // A quote in the next part of a partial input would still be in the string
+                let more_quotes = self.triple_quoted && hashes == 0 && extra_quotes < 2;
+                if input.is_partial() && more_quotes && chars.rest.is_empty() {
+                    return Err(incomplete());
+                }
+                break;
+            }
+
+            let char_start = chars.consumed;
             match chars.next() {
-                Some('"') => {
-                    acc += 1;
-                    break;
+                Some('\\') if !self.raw => {
+                    let escaped = self.escape(&mut chars);
+                    let c = escaped.map_err(|message| {
+                        if input.is_partial() && chars.rest.is_empty() {
+                            return incomplete();
+                        }
+                        error_at(ParsingErrorKind::InvalidEscapeSequence(message), char_start)
+                    })?;
+                    decoded.push(c);
                 }
-                Some('\\') => {
-                    let _ = chars.next().unwrap();
-                    acc += 1;
+                Some('\n' | '\r') if !self.newlines => {
+                    let kind = ParsingErrorKind::PatternNotFound(
+                        "found a new line before the closing quote".to_string(),
+                    );
+                    return Err(error_at(kind, char_start));
                 }
-                Some(_) => acc += 1,
+                Some(c) => decoded.push(c),
+                None if input.is_partial() => return Err(incomplete()),
                 None => {
-                    let kind = crate::errors::ParsingErrorKind::PatternNotFound(
-                        "Did not find closing quote \"".to_string(),
-                    );
-                    return Err(ParsingError::new(kind, rest.line, rest.col));
+                    let kind = ParsingErrorKind::PatternNotFound(format!(
+                        "Did not find closing quote {closing}"
+                    ));
+                    return Err(error_at(kind, chars.consumed));
                 }
             };
         }
 
-        Ok((
-            // Do not include the '"' as part of the string
-            input.source.chars().skip(1).take(acc - 2).collect(),
-            input.clone().char_offset(acc),
-        ))
+        Ok((decoded, input.clone().char_offset(chars.consumed)))
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class("string".to_string())
     }
 }
 
 pub fn string_parser() -> impl Parser<Output = String> {
-    StringParser
+    StringParser::new()
 }
 
 #[cfg(test)]
 mod string_parser_test {
-    use crate::parsers::string_p::string_parser;
+    use crate::errors::{ParsingError, ParsingErrorKind};
+    use crate::inputs::Input;
+    use crate::parsers::string_p::{string_parser, StringParser};
     use crate::traits::Parser;
 
+    fn decode(source: &str) -> String {
+        StringParser::new().parse(&source.into()).unwrap().0
+    }
+
+    fn escape_error(parser: StringParser, source: &str) -> ParsingError {
+        let err = parser.parse(&source.into()).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ParsingErrorKind::InvalidEscapeSequence(_)
+        ));
+        err
+    }
+
     #[test]
     fn string_parser_test() {
         let sp = string_parser();
@@ -61,4 +422,157 @@ mod string_parser_test {
         assert_eq!(inp.source, " and this is the rest".to_string());
         assert_eq!(inp.col, 21);
     }
+
+    #[test]
+    fn simple_escapes() {
+        assert_eq!(decode(r#""a\"b""#), "a\"b");
+        assert_eq!(decode(r#""\n\r\t\\\/\'""#), "\n\r\t\\/'");
+        assert_eq!(decode(r#""\b\f\0""#), "\u{8}\u{c}\0");
+
+        let (p, rest) = string_parser()
+            .parse(&r#""say \"hi\"" rest"#.into())
+            .unwrap();
+        assert_eq!(p, "say \"hi\"");
+        assert_eq!(rest.source, " rest");
+        assert_eq!(rest.col, 12);
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        assert_eq!(decode(r#""\u0041\u00e9""#), "Aé");
+        assert_eq!(decode(r#""\uD83D\uDE00""#), "\u{1F600}");
+        assert_eq!(decode(r#""\u{1F600}\u{41}""#), "\u{1F600}A");
+
+        // Lone surrogates are rejected
+        escape_error(StringParser::new(), r#""\uD83D""#);
+        escape_error(StringParser::new(), r#""\uDE00""#);
+        escape_error(StringParser::new(), r#""\uD83DA""#);
+        // Not a scalar value, or too many digits
+        escape_error(StringParser::new(), r#""\u{D800}""#);
+        escape_error(StringParser::new(), r#""\u{1234567}""#);
+        escape_error(StringParser::new(), r#""\u12""#);
+    }
+
+    #[test]
+    fn hex_and_octal_escapes() {
+        assert_eq!(decode(r#""\x41\x62""#), "Ab");
+        assert_eq!(decode(r#""\101\60\0""#), "A0\0");
+        assert_eq!(decode(r#""\1018""#), "A8");
+
+        escape_error(StringParser::new(), r#""\400""#);
+        escape_error(StringParser::new(), r#""\x4""#);
+        escape_error(StringParser::new().hex(false), r#""\x41""#);
+        escape_error(StringParser::new().braced_unicode(false), r#""\u{41}""#);
+        escape_error(StringParser::new().unicode(false), r#""\u0041""#);
+
+        // Without octal escapes, \0 is still the null character
+        let sp = StringParser::new().octal(false);
+        assert_eq!(sp.parse(&r#""\0""#.into()).unwrap().0, "\0");
+        escape_error(sp, r#""\101""#);
+    }
+
+    #[test]
+    fn invalid_escapes_are_positioned() {
+        let err = escape_error(StringParser::new(), r#""abc\q""#);
+        assert_eq!(err.col(), 4);
+
+        let err = escape_error(StringParser::new(), r#""ab\u12g4""#);
+        assert_eq!(err.col(), 3);
+    }
+
+    #[test]
+    fn never_panics_on_unterminated_input() {
+        for source in [
+            r#""abc\"#,
+            r#""abc"#,
+            r#"""#,
+            r#""\u"#,
+            r#""\uD83D\"#,
+            r#""\u{12"#,
+        ] {
+            assert!(StringParser::new().parse(&source.into()).is_err());
+        }
+    }
+
+    #[test]
+    fn other_quote_characters() {
+        let sp = StringParser::new().quote('\'');
+        let (p, rest) = sp.parse(&r#"'it\'s "quoted"' rest"#.into()).unwrap();
+        assert_eq!(p, "it's \"quoted\"");
+        assert_eq!(rest.source, " rest");
+        assert!(sp.parse(&r#""double""#.into()).is_err());
+
+        let sp = StringParser::new().quote('`');
+        let (p, _) = sp.parse(&r"`a\`b`".into()).unwrap();
+        assert_eq!(p, "a`b");
+    }
+
+    #[test]
+    fn raw_strings() {
+        let sp = StringParser::new().raw();
+        let (p, rest) = sp.parse(&r#"r"a\nb" rest"#.into()).unwrap();
+        assert_eq!(p, r"a\nb");
+        assert_eq!(rest.source, " rest");
+
+        let (p, rest) = sp.parse(&r###"r##"a "# b"## rest"###.into()).unwrap();
+        assert_eq!(p, r##"a "# b"##);
+        assert_eq!(rest.source, " rest");
+        assert_eq!(rest.col, 13);
+
+        assert!(sp.parse(&r#""not raw""#.into()).is_err());
+        assert!(sp.parse(&r###"r##"unterminated"#"###.into()).is_err());
+    }
+
+    #[test]
+    fn triple_quoted_strings() {
+        let sp = StringParser::new().triple_quoted();
+        let (p, rest) = sp
+            .parse(&"\"\"\"one \"two\"\nthree\\t\"\"\" rest".into())
+            .unwrap();
+        assert_eq!(p, "one \"two\"\nthree\t");
+        assert_eq!(rest.source, " rest");
+
+        let sp = sp.trim_first_newline(true);
+        let (p, _) = sp.parse(&"\"\"\"\nline\n\"\"\"".into()).unwrap();
+        assert_eq!(p, "line\n");
+
+        let sp = StringParser::new().quote('\'').triple_quoted().raw();
+        let (p, _) = sp.parse(&r"r'''C:\dir'''".into()).unwrap();
+        assert_eq!(p, r"C:\dir");
+
+        assert!(sp.parse(&"r'''\nunterminated''".into()).is_err());
+    }
+
+    #[test]
+    fn quotes_before_triple_closing_quotes() {
+        let sp = StringParser::new().triple_quoted();
+        assert_eq!(sp.parse(&r#""""a"""""#.into()).unwrap().0, "a\"");
+        assert_eq!(sp.parse(&r#"""""a""""""#.into()).unwrap().0, "\"a\"\"");
+        // Only two of them, and the rest follow the string
+        let (p, rest) = sp.parse(&r#""""a"""""" x"#.into()).unwrap();
+        assert_eq!(p, "a\"\"");
+        assert_eq!(rest.source, "\" x");
+
+        let sp = StringParser::new().quote('\'').triple_quoted().raw();
+        assert_eq!(sp.parse(&"r'''a''''".into()).unwrap().0, "a'");
+
+        // More quotes may follow in the next part of a partial input
+        let sp = StringParser::new().triple_quoted();
+        let partial = |source: &str| Input::from(source).partial(true);
+        assert!(sp
+            .parse(&partial(r#""""a""""#))
+            .is_err_and(|e| e.is_incomplete()));
+        assert!(sp.parse(&partial(r#""""a""" "#)).is_ok());
+        assert!(sp.parse(&partial(r#""""a""""""#)).is_ok());
+    }
+
+    #[test]
+    fn disallowed_newlines() {
+        let sp = StringParser::new().allow_newlines(false);
+        assert_eq!(sp.parse(&r#""a\nb""#.into()).unwrap().0, "a\nb");
+
+        let err = sp.parse(&"\"ab\ncd\"".into()).unwrap_err();
+        assert_eq!(err.col(), 3);
+        assert!(string_parser().parse(&"\"ab\ncd\"".into()).is_ok());
+    }
 }
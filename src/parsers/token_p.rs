@@ -0,0 +1,372 @@
+use std::fmt::Debug;
+
+use crate::{
+    describe::Description,
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::{Input, ParserInput, Position, Span, TokenInput},
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+use super::span_p::Spanned;
+
+// Parsing usually happens in one go over the characters of an `Input`. Alternatively, a
+// tokenizer made from character parsers can first split the source into tokens, and then
+// any of the generic combinators (`and_then`, `otherwise`, `repeat`, ...) can be used
+// over the resulting `TokenInput`, together with the primitives in this module.
+
+/// The tokens of some source, together with the position of the end of the source
+#[derive(Debug, Clone)]
+pub struct Tokens<T> {
+    pub tokens: Vec<Spanned<T>>,
+    pub end: Position,
+}
+
+impl<T> Tokens<T> {
+    /// An input to parse the tokens with
+    pub fn input(&self) -> TokenInput<'_, T> {
+        TokenInput::new(&self.tokens, self.end)
+    }
+}
+
+/// Split some input into tokens, skipping any trivia (such as whitespace or comments) before
+/// and between the tokens.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::lexeme_p::{identifier, TokenParser};
+/// use parlib::parsers::token_p::{token, tokenize};
+/// use parlib::traits::Parser;
+///
+/// let tokens = tokenize(identifier(), TokenParser::new().whitespace(), &"let x".into()).unwrap();
+/// assert_eq!(tokens.tokens[1].node, "x".to_string());
+/// assert_eq!(tokens.tokens[1].span.start.col, 4);
+///
+/// let (parsed, _) = token("let".to_string()).parse(&tokens.input()).unwrap();
+/// assert_eq!(parsed, "let".to_string());
+/// ```
+pub fn tokenize<P, W>(token: P, trivia: W, input: &Input) -> Result<Tokens<P::Output>, ParsingError>
+where
+    P: Parser,
+    W: Parser,
+{
+    let mut tokens = vec![];
+    let (_, mut rest) = trivia.parse(input)?;
+    while !rest.source.is_empty() {
+        let start = rest.position();
+        let (parsed, after) = token.parse(&rest)?;
+        if after.offset == rest.offset {
+            let kind = ParsingErrorKind::PatternNotFound(
+                "the tokenizer did not consume any input".to_string(),
+            );
+            return Err(ParsingError::new(kind, start.line, start.col));
+        }
+        tokens.push(Spanned::new(parsed, Span::new(start, after.position())));
+        (_, rest) = trivia.parse(&after)?;
+    }
+
+    Ok(Tokens {
+        tokens,
+        end: rest.position(),
+    })
+}
+
+fn expected_error<T>(input: &TokenInput<'_, T>, label: String) -> ParsingError
+where
+    T: Debug,
+{
+    let at = input.position();
+    ParsingError::new(ParsingErrorKind::Expected(label), at.line, at.col)
+}
+
+/// Parse a single token, if it is equal to some token, see `token`
+#[derive(Debug, Clone)]
+pub struct MatchToken<T> {
+    token: T,
+}
+
+impl<'a, T> Parser<TokenInput<'a, T>> for MatchToken<T>
+where
+    T: PartialEq + Clone + Debug,
+{
+    type Output = T;
+    fn parse(&self, input: &TokenInput<'a, T>) -> ParserRes<T, ParsingError, TokenInput<'a, T>> {
+        match input.tokens.first() {
+            Some(first) if first.node == self.token => {
+                Ok((first.node.clone(), input.token_offset(1)))
+            }
+            _ => Err(expected_error(input, format!("{:?}", self.token))),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Literal(format!("{:?}", self.token))
+    }
+}
+
+/// Parse a single token, if it is equal to `token`
+pub fn token<T>(token: T) -> MatchToken<T> {
+    MatchToken { token }
+}
+
+/// Parse a single token, if `select` maps it to some value, see `select`
+pub struct Select<F> {
+    select: F,
+    label: String,
+}
+
+impl<F> Select<F> {
+    /// Describe the tokens that this parser accepts, for use in errors
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+}
+
+impl<'a, T, O, F> Parser<TokenInput<'a, T>> for Select<F>
+where
+    T: Debug,
+    O: Debug,
+    F: Fn(&T) -> Option<O>,
+{
+    type Output = O;
+    fn parse(&self, input: &TokenInput<'a, T>) -> ParserRes<O, ParsingError, TokenInput<'a, T>> {
+        match input
+            .tokens
+            .first()
+            .and_then(|first| (self.select)(&first.node))
+        {
+            Some(selected) => Ok((selected, input.token_offset(1))),
+            None => Err(expected_error(input, self.label.clone())),
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Class(self.label.clone())
+    }
+}
+
+/// Parse a single token, if `select` maps it to some value. This is useful for tokens that
+/// carry some data, such as identifiers or numbers.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::inputs::{Position, Span, TokenInput};
+/// use parlib::parsers::span_p::Spanned;
+/// use parlib::parsers::token_p::select;
+/// use parlib::traits::Parser;
+///
+/// #[derive(Debug)]
+/// enum Token { Number(i64), Plus }
+///
+/// let tokens = [Spanned::new(Token::Number(3), Span::default())];
+/// let number = select(|t| match t { Token::Number(n) => Some(*n), _ => None }).label("a number");
+/// let (parsed, _) = number.parse(&TokenInput::new(&tokens, Position::default())).unwrap();
+/// assert_eq!(parsed, 3);
+/// ```
+pub fn select<T, O, F>(select: F) -> Select<F>
+where
+    F: Fn(&T) -> Option<O>,
+{
+    Select {
+        select,
+        label: "a token".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test_tokens {
+    use super::{select, token, tokenize, Tokens};
+    use crate::{
+        errors::ParsingError,
+        errors::ParsingErrorKind,
+        inputs::{Position, Span, TokenInput},
+        parsers::{
+            and_p::KeepFirstOutputOnly,
+            lexeme_p::{identifier, TokenParser},
+            number_p::integer,
+            repeat_p::{count, many_till, repeat},
+            span_p::Spanned,
+            trie_p::literals_with,
+        },
+        traits::Parser,
+        type_alias::ParserRes,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Tok {
+        Num(i64),
+        Ident(String),
+        Plus,
+        Star,
+        LParen,
+        RParen,
+        Dedent,
+    }
+
+    fn lex(source: &str) -> Tokens<Tok> {
+        let symbols = literals_with(&[
+            ("+", Tok::Plus),
+            ("*", Tok::Star),
+            ("(", Tok::LParen),
+            (")", Tok::RParen),
+        ]);
+        let tok = symbols
+            .otherwise(integer::<i64>().with_mapping(&Tok::Num))
+            .otherwise(identifier().with_mapping(&Tok::Ident));
+        let trivia = TokenParser::new().line_comment("#").whitespace();
+        tokenize(tok, trivia, &source.into()).unwrap()
+    }
+
+    // sum = product ("+" product)*, product = atom ("*" atom)*, atom = num | ident | "(" sum ")"
+    struct Sum;
+    struct Atom;
+
+    impl<'a> Parser<TokenInput<'a, Tok>> for Atom {
+        type Output = i64;
+        fn parse(
+            &self,
+            input: &TokenInput<'a, Tok>,
+        ) -> ParserRes<i64, ParsingError, TokenInput<'a, Tok>> {
+            let number = select(|t: &Tok| match t {
+                Tok::Num(n) => Some(*n),
+                Tok::Ident(name) => Some(name.len() as i64),
+                _ => None,
+            })
+            .label("a number or a variable");
+            let group = Sum
+                .preceed(token(Tok::LParen))
+                .and_then(token(Tok::RParen))
+                .combine(KeepFirstOutputOnly);
+            number.otherwise(group).parse(input)
+        }
+    }
+
+    impl<'a> Parser<TokenInput<'a, Tok>> for Sum {
+        type Output = i64;
+        fn parse(
+            &self,
+            input: &TokenInput<'a, Tok>,
+        ) -> ParserRes<i64, ParsingError, TokenInput<'a, Tok>> {
+            let product = Atom
+                .and_then(repeat(Atom.preceed(token(Tok::Star))).minm(0))
+                .with_mapping(&|(first, rest)| first * rest.iter().product::<i64>());
+            let (first, rest) = product.parse(input)?;
+            let (terms, rest) = repeat(Sum.preceed(token(Tok::Plus)))
+                .minm(0)
+                .maxm(1)
+                .parse(&rest)?;
+            Ok((first + terms.iter().sum::<i64>(), rest))
+        }
+    }
+
+    #[test]
+    fn tokenizer_spans() {
+        let tokens = lex("foo + 12 # comment\n * (x)");
+        let kinds = tokens
+            .tokens
+            .iter()
+            .map(|t| t.node.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Tok::Ident("foo".to_string()),
+                Tok::Plus,
+                Tok::Num(12),
+                Tok::Star,
+                Tok::LParen,
+                Tok::Ident("x".to_string()),
+                Tok::RParen,
+            ]
+        );
+        let star = tokens.tokens[3].span;
+        assert_eq!(
+            (star.start.line, star.start.col, star.start.offset),
+            (1, 1, 20)
+        );
+        assert_eq!((tokens.end.line, tokens.end.col), (1, 6));
+
+        let bad = "1 + $";
+        let trivia = TokenParser::new().whitespace();
+        let err = tokenize(integer::<i64>(), trivia, &bad.into()).unwrap_err();
+        assert_eq!((err.line(), err.col()), (0, 2));
+    }
+
+    #[test]
+    fn generic_combinators_over_tokens() {
+        let tokens = lex("a + 2");
+        let input = tokens.input();
+        let (parsed, rest) = count(3, select(|t: &Tok| Some(t.clone())))
+            .parse(&input)
+            .unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert!(rest.is_empty());
+
+        let (parsed, _) = token(Tok::Plus)
+            .otherwise(token(Tok::Ident("a".to_string())))
+            .parse(&input)
+            .unwrap();
+        assert_eq!(parsed, Tok::Ident("a".to_string()));
+    }
+
+    #[test]
+    fn tokens_without_text() {
+        // Closing several blocks at once makes dedents that all share an empty span
+        let dedent = Spanned::new(Tok::Dedent, Span::default());
+        let tokens = vec![
+            dedent.clone(),
+            dedent.clone(),
+            dedent,
+            lex("x").tokens[0].clone(),
+        ];
+        let input = TokenInput::new(&tokens, Position::default());
+
+        let (dedents, rest) = repeat(token(Tok::Dedent)).parse(&input).unwrap();
+        assert_eq!(dedents.len(), 3);
+        assert_eq!(rest.tokens.len(), 1);
+
+        let ident = token(Tok::Ident("x".to_string()));
+        let (dedents, rest) = many_till(token(Tok::Dedent), ident).parse(&input).unwrap();
+        assert_eq!(dedents.len(), 3);
+        assert!(rest.is_empty());
+
+        let parsed = token(Tok::Dedent)
+            .iter(&input.token_offset(1))
+            .take_while(Result::is_ok)
+            .count();
+        assert_eq!(parsed, 2);
+    }
+
+    #[test]
+    fn expression_over_tokens() {
+        let tokens = lex("2 * (1 + abc) + 3");
+        let (value, rest) = Sum.parse(&tokens.input()).unwrap();
+        assert_eq!(value, 11);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn errors_at_source_positions() {
+        let tokens = lex("1 +\n  * 2");
+        let err = token(Tok::Num(1))
+            .and_then(token(Tok::Plus))
+            .and_then(select(|t: &Tok| matches!(t, Tok::Num(_)).then_some(())).label("a number"))
+            .parse(&tokens.input())
+            .unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("a number".to_string())
+        );
+        assert_eq!((err.line(), err.col()), (1, 2));
+
+        // Running out of tokens reports the end of the source
+        let tokens = lex("1 +  ");
+        let err = count(3, select(|t: &Tok| Some(t.clone())))
+            .parse(&tokens.input())
+            .unwrap_err();
+        assert_eq!((err.line(), err.col()), (0, 5));
+    }
+}
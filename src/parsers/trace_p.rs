@@ -0,0 +1,366 @@
+use std::{cell::RefCell, fmt::Display};
+
+use crate::{
+    describe::Description,
+    errors::ParsingError,
+    inputs::{ParserInput, Position},
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+// Tracing is off until `start` is called, and each thread has its own tracer, so traced
+// parsers cost very little when no one is looking at them.
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Default)]
+struct Tracer {
+    /// The entries of the traced parsers that are still running, innermost last
+    open: Vec<TraceEntry>,
+    finished: Vec<TraceEntry>,
+}
+
+/// Start recording the traced parsers that run on this thread, see `Traced`. Anything
+/// that was recorded before is discarded.
+pub fn start() {
+    TRACER.with(|tracer| *tracer.borrow_mut() = Some(Tracer::default()));
+}
+
+/// Stop recording, and get everything that was recorded since `start`
+pub fn finish() -> Trace {
+    let tracer = TRACER.with(|tracer| tracer.borrow_mut().take());
+    let mut tracer = tracer.unwrap_or_default();
+
+    // Parsers that are still running (if `finish` was called from inside a parser) are
+    // closed where they are
+    while let Some(entry) = tracer.open.pop() {
+        match tracer.open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => tracer.finished.push(entry),
+        }
+    }
+    Trace {
+        entries: tracer.finished,
+    }
+}
+
+/// Record the traced parsers that run inside of `f`
+pub fn record<T>(f: impl FnOnce() -> T) -> (T, Trace) {
+    start();
+    let out = f();
+    (out, finish())
+}
+
+/// One run of a traced parser
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub name: String,
+    pub start: Position,
+    /// Where the parser stopped. If it failed, this is the line and column of the error,
+    /// with the offset of `start`
+    pub end: Position,
+    pub success: bool,
+    /// The source that the parser consumed, for inputs that have source text
+    pub consumed: Option<String>,
+    pub error: Option<String>,
+    /// The traced parsers that ran inside of this one
+    pub children: Vec<TraceEntry>,
+}
+
+/// Everything that was recorded by the tracer, as a tree of entries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trace {
+    pub entries: Vec<TraceEntry>,
+}
+
+fn write_text(out: &mut String, entry: &TraceEntry, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let start = entry.start;
+    out.push_str(&format!(
+        "{indent}> {} at {}:{}\n",
+        entry.name, start.line, start.col
+    ));
+    for child in &entry.children {
+        write_text(out, child, depth + 1);
+    }
+
+    let end = entry.end;
+    if entry.success {
+        out.push_str(&format!(
+            "{indent}< {} ok at {}:{}",
+            entry.name, end.line, end.col
+        ));
+        if let Some(consumed) = &entry.consumed {
+            out.push_str(&format!(", consumed {consumed:?}"));
+        }
+    } else {
+        out.push_str(&format!(
+            "{indent}< {} failed at {}:{}",
+            entry.name, end.line, end.col
+        ));
+        if let Some(error) = &entry.error {
+            out.push_str(&format!(": {error}"));
+        }
+    }
+    out.push('\n');
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_position(at: Position) -> String {
+    format!(
+        "{{\"line\":{},\"col\":{},\"offset\":{}}}",
+        at.line, at.col, at.offset
+    )
+}
+
+fn json_entry(entry: &TraceEntry) -> String {
+    let optional = |s: &Option<String>| s.as_deref().map_or("null".to_string(), json_string);
+    let children = entry.children.iter().map(json_entry).collect::<Vec<_>>();
+    format!(
+        "{{\"name\":{},\"start\":{},\"end\":{},\"success\":{},\"consumed\":{},\"error\":{},\"children\":[{}]}}",
+        json_string(&entry.name),
+        json_position(entry.start),
+        json_position(entry.end),
+        entry.success,
+        optional(&entry.consumed),
+        optional(&entry.error),
+        children.join(",")
+    )
+}
+
+impl Trace {
+    /// The trace as an indented tree, with a line for when each parser started and one
+    /// for when it stopped
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.entries {
+            write_text(&mut out, entry, 0);
+        }
+        out
+    }
+
+    /// The trace as a JSON array of entries, each of which has the fields of
+    /// `TraceEntry`
+    pub fn to_json(&self) -> String {
+        let entries = self.entries.iter().map(json_entry).collect::<Vec<_>>();
+        format!("[{}]", entries.join(","))
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_text())
+    }
+}
+
+/// Record every run of some parser in the tracer, if the tracer has been started (see
+/// `start`). This can be added to any parser with `Parser::trace`.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::{trace_p, ParseMatch, ParseWhile};
+/// use parlib::traits::Parser;
+///
+/// let number = ParseWhile(|c| c.is_numeric()).trace("number");
+/// let sum = number.clone().and_then(ParseMatch("+")).and_then(number).trace("sum");
+///
+/// let (parsed, trace) = trace_p::record(|| sum.parse(&"1+x".into()));
+/// assert!(parsed.is_err());
+/// assert_eq!(
+///     trace.to_text(),
+///     "> sum at 0:0\n\
+///     \x20 > number at 0:0\n\
+///     \x20 < number ok at 0:1, consumed \"1\"\n\
+///     \x20 > number at 0:2\n\
+///     \x20 < number failed at 0:2: PatternNotFound(\"no characters matched predicate\")\n\
+///     < sum failed at 0:2: PatternNotFound(\"no characters matched predicate\")\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Traced<P> {
+    parser: P,
+    name: String,
+}
+
+impl<P> Traced<P> {
+    pub fn new(parser: P, name: &str) -> Self {
+        Traced {
+            parser,
+            name: name.to_string(),
+        }
+    }
+}
+
+fn enter(name: &str, start: Position) -> bool {
+    TRACER.with(|tracer| match tracer.borrow_mut().as_mut() {
+        Some(tracer) => {
+            tracer.open.push(TraceEntry {
+                name: name.to_string(),
+                start,
+                end: start,
+                success: false,
+                consumed: None,
+                error: None,
+                children: vec![],
+            });
+            true
+        }
+        None => false,
+    })
+}
+
+fn exit(end: Position, consumed: Option<String>, error: Option<&ParsingError>) {
+    TRACER.with(|tracer| {
+        let mut tracer = tracer.borrow_mut();
+        // The tracer may have been stopped, or restarted, by the parser
+        let Some(tracer) = tracer.as_mut() else {
+            return;
+        };
+        let Some(mut entry) = tracer.open.pop() else {
+            return;
+        };
+
+        entry.end = end;
+        entry.success = error.is_none();
+        entry.consumed = consumed;
+        entry.error = error.map(|e| format!("{:?}", e.kind()));
+        match tracer.open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => tracer.finished.push(entry),
+        }
+    })
+}
+
+impl<I, P> Parser<I> for Traced<P>
+where
+    I: ParserInput,
+    P: Parser<I>,
+{
+    type Output = P::Output;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I> {
+        if !enter(&self.name, input.position()) {
+            return self.parser.parse(input);
+        }
+
+        let parsed = self.parser.parse(input);
+        match &parsed {
+            Ok((_, rest)) => exit(rest.position(), input.consumed(rest), None),
+            Err(e) => {
+                let at = Position {
+                    line: e.line(),
+                    col: e.col(),
+                    ..input.position()
+                };
+                exit(at, None, Some(e))
+            }
+        }
+        parsed
+    }
+
+    fn describe(&self) -> Description {
+        self.parser.describe()
+    }
+}
+
+#[cfg(test)]
+mod test_trace {
+    use super::{finish, record, start, TraceEntry};
+    use crate::{
+        inputs::Position,
+        parsers::{number_p::integer, ParseMatch},
+        traits::Parser,
+    };
+
+    #[test]
+    fn nested_entries() {
+        let digit = integer::<u8>().trace("digit");
+        let list = ParseMatch("[")
+            .and_then(digit.otherwise(ParseMatch("x").trace("x").with_mapping(&|_| 0)))
+            .and_then(ParseMatch("]"))
+            .trace("list");
+
+        let (parsed, trace) = record(|| list.parse(&"[x]".into()));
+        assert!(parsed.is_ok());
+        assert_eq!(trace.entries.len(), 1);
+
+        let list = &trace.entries[0];
+        assert!(list.success);
+        assert_eq!(list.consumed.as_deref(), Some("[x]"));
+        assert_eq!(
+            list.end,
+            Position {
+                line: 0,
+                col: 3,
+                offset: 3
+            }
+        );
+        let names = list
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["digit", "x"]);
+        assert!(!list.children[0].success);
+        assert!(list.children[0].error.is_some());
+        assert!(list.children[1].success);
+    }
+
+    #[test]
+    fn only_records_when_started() {
+        let digit = integer::<u8>().trace("digit");
+        assert!(digit.parse(&"1".into()).is_ok());
+        assert!(finish().entries.is_empty());
+
+        start();
+        assert!(digit.parse(&"1".into()).is_ok());
+        assert!(digit.parse(&"2".into()).is_ok());
+        assert_eq!(finish().entries.len(), 2);
+        assert!(finish().entries.is_empty());
+    }
+
+    #[test]
+    fn json_export() {
+        let quoted = ParseMatch("\"a\n\"").trace("quo\"ted");
+        let (_, trace) = record(|| quoted.parse(&"\"a\n\"".into()));
+        assert_eq!(
+            trace.to_json(),
+            concat!(
+                r#"[{"name":"quo\"ted","start":{"line":0,"col":0,"offset":0},"#,
+                r#""end":{"line":1,"col":1,"offset":4},"success":true,"#,
+                r#""consumed":"\"a\n\"","error":null,"children":[]}]"#
+            )
+        );
+
+        let entry = TraceEntry {
+            name: "x".to_string(),
+            start: Position::default(),
+            end: Position::default(),
+            success: false,
+            consumed: None,
+            error: Some("bad\u{1}".to_string()),
+            children: vec![],
+        };
+        let trace = super::Trace {
+            entries: vec![entry],
+        };
+        assert!(trace.to_json().contains(r#""error":"bad\u0001""#));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, fmt::Debug};
+
+use crate::{
+    describe::Description,
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    inputs::Input,
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+#[derive(Debug, Clone)]
+struct TrieNode<T> {
+    children: BTreeMap<char, TrieNode<T>>,
+    value: Option<T>,
+}
+
+impl<T> Default for TrieNode<T> {
+    fn default() -> Self {
+        TrieNode {
+            children: BTreeMap::new(),
+            value: None,
+        }
+    }
+}
+
+/// Parse one out of a set of literals, always picking the longest one that matches.
+///
+/// The literals are compiled into a trie, so the time taken does not depend on the number
+/// of literals, and the order they are given in does not matter ("<" will never be picked
+/// over "<="). If none of them match, the error will list all of the literals.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::trie_p::{literals, literals_with};
+/// use parlib::traits::Parser;
+///
+/// let ops = literals(&["<", "<=", "=="]);
+/// let (parsed, rest) = ops.parse(&"<= 2".into()).unwrap();
+/// assert_eq!(parsed, "<=".to_string());
+/// assert_eq!(rest.source, " 2".to_string());
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum Op { Lt, Le }
+///
+/// let ops = literals_with(&[("<", Op::Lt), ("<=", Op::Le)]);
+/// assert_eq!(ops.parse(&"<1".into()).unwrap().0, Op::Lt);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Literals<T> {
+    root: TrieNode<T>,
+    expected: Vec<String>,
+}
+
+impl<T> Literals<T> {
+    pub fn new() -> Self {
+        Literals {
+            root: TrieNode::default(),
+            expected: vec![],
+        }
+    }
+
+    /// Add a literal, and the value that will be returned when it is parsed. If the literal
+    /// was already added, its value is replaced.
+    pub fn insert(mut self, literal: &str, value: T) -> Self {
+        let node = literal.chars().fold(&mut self.root, |node, c| {
+            node.children.entry(c).or_default()
+        });
+        if node.value.replace(value).is_none() {
+            self.expected.push(literal.to_string());
+        }
+        self
+    }
+}
+
+impl<T> Default for Literals<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Parser for Literals<T>
+where
+    T: Clone + Debug,
+{
+    type Output = T;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (value, 0));
+        let mut chars = input.source.chars().enumerate();
+        loop {
+            let Some((count, c)) = chars.next() else {
+                // A longer literal may continue in the next part of a partial input
+                if input.is_partial() && !node.children.is_empty() {
+                    return Err(input.incomplete(Needed::Unknown));
+                }
+                break;
+            };
+            let Some(next) = node.children.get(&c) else {
+                break;
+            };
+            node = next;
+            if let Some(value) = &node.value {
+                longest = Some((value, count + 1));
+            }
+        }
+
+        match longest {
+            Some((value, len)) => Ok((value.clone(), input.clone().char_offset(len))),
+            None => {
+                let expected = self
+                    .expected
+                    .iter()
+                    .map(|literal| format!("{literal:?}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let kind = ParsingErrorKind::Expected(format!("one of {expected}"));
+                Err(ParsingError::new(kind, input.line, input.col))
+            }
+        }
+    }
+
+    fn describe(&self) -> Description {
+        Description::Choice(
+            self.expected
+                .iter()
+                .map(|literal| Description::Literal(literal.clone()))
+                .collect(),
+        )
+    }
+}
+
+/// Parse the longest matching literal out of a set, see `Literals`
+pub fn literals(literals: &[&str]) -> Literals<String> {
+    literals.iter().fold(Literals::new(), |trie, literal| {
+        trie.insert(literal, literal.to_string())
+    })
+}
+
+/// Parse the longest matching literal out of a set, mapping each literal to some value
+pub fn literals_with<T>(pairs: &[(&str, T)]) -> Literals<T>
+where
+    T: Clone,
+{
+    pairs
+        .iter()
+        .fold(Literals::new(), |trie, (literal, value)| {
+            trie.insert(literal, value.clone())
+        })
+}
+
+#[cfg(test)]
+mod test_literals {
+    use super::{literals, literals_with, Literals};
+    use crate::{errors::ParsingErrorKind, traits::Parser};
+
+    #[test]
+    fn longest_match() {
+        let ops = literals(&["<", "<=", "<<=", "=", "=="]);
+        for (source, expected, rest) in [
+            ("<", "<", ""),
+            ("<=", "<=", ""),
+            ("<<=1", "<<=", "1"),
+            ("<<1", "<", "<1"),
+            ("===", "==", "="),
+        ] {
+            let (p, r) = ops.parse(&source.into()).unwrap();
+            assert_eq!(p, expected, "{source}");
+            assert_eq!(r.source, rest, "{source}");
+            assert_eq!(r.col, source.len() - rest.len());
+        }
+    }
+
+    #[test]
+    fn unicode_literals() {
+        let arrows = literals(&["\u{2192}", "\u{2192}\u{2192}", "->"]);
+        let (p, rest) = arrows.parse(&"\u{2192}\u{2192}x".into()).unwrap();
+        assert_eq!(p, "\u{2192}\u{2192}");
+        assert_eq!(rest.source, "x");
+        assert_eq!(rest.col, 2);
+    }
+
+    #[test]
+    fn mapped_values() {
+        let bools = literals_with(&[("true", true), ("false", false), ("yes", true)]);
+        assert!(bools.parse(&"yes".into()).unwrap().0);
+        assert!(!bools.parse(&"false".into()).unwrap().0);
+
+        let numbers = Literals::new()
+            .insert("one", 1)
+            .insert("two", 2)
+            .insert("one", 11);
+        assert_eq!(numbers.parse(&"one".into()).unwrap().0, 11);
+    }
+
+    #[test]
+    fn expected_set() {
+        let ops = literals(&["+", "-", "+="]);
+        let err = ops.parse(&"*".into()).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("one of \"+\", \"-\", \"+=\"".to_string())
+        );
+        assert!(literals(&["ab"]).parse(&"a".into()).is_err());
+    }
+}
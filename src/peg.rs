@@ -0,0 +1,1618 @@
+//! Grammars that are only known at runtime, such as user supplied grammars, see `Peg`
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    describe::{Description, Grammar},
+    errors::{Needed, ParsingError, ParsingErrorKind},
+    grammar::keep_furthest,
+    inputs::{Input, ParserInput, Position, Span},
+    parsers::{
+        char_p::one_of,
+        lexeme_p::{identifier, TokenParser, Whitespace},
+        span_p::Spanned,
+        string_p::StringParser,
+        ParseMatch,
+    },
+    traits::Parser,
+    type_alias::ParserRes,
+};
+
+/// A node of the concrete syntax tree that a `Peg` parses: a rule, the part of the source
+/// that it matched, and the rules that matched inside of it.
+///
+/// The children are shared with the parses that remember them, see `Peg::reparse`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub rule: String,
+    pub span: Span,
+    pub children: Vec<Arc<Node>>,
+}
+
+impl Node {
+    /// The text that the node matched, in the source that it was parsed from
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.span.start.offset..self.span.end.offset]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GrammarErrorKind {
+    /// The text of the grammar could not be parsed
+    Syntax(ParsingErrorKind),
+    /// The grammar has no rules
+    NoRules,
+    DuplicateRule(String),
+    UndefinedRule(String),
+    /// A character class with a range that ends before it starts, such as `z-a`
+    InvalidRange(String),
+    /// A rule that can call itself without consuming anything, which would never stop. The
+    /// rules of the cycle are listed in the order that they call each other.
+    LeftRecursion(Vec<String>),
+}
+
+/// An error in the text of a grammar, at the line and column where it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrammarError {
+    kind: GrammarErrorKind,
+    line: usize,
+    col: usize,
+}
+
+impl GrammarError {
+    pub fn new(kind: GrammarErrorKind, line: usize, col: usize) -> Self {
+        GrammarError { kind, line, col }
+    }
+
+    pub fn kind(&self) -> &GrammarErrorKind {
+        &self.kind
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+
+    fn at(kind: GrammarErrorKind, span: Span) -> Self {
+        GrammarError::new(kind, span.start.line, span.start.col)
+    }
+}
+
+impl From<ParsingError> for GrammarError {
+    fn from(error: ParsingError) -> Self {
+        let (line, col) = (error.line(), error.col());
+        GrammarError::new(GrammarErrorKind::Syntax(error.kind().clone()), line, col)
+    }
+}
+
+/// The expressions of a grammar, as written
+#[derive(Debug, Clone)]
+enum Raw {
+    Literal(String),
+    /// The text of a character class, with its brackets
+    Class(Spanned<String>),
+    Any,
+    Reference(Spanned<String>),
+    Sequence(Vec<Raw>),
+    Choice(Vec<Raw>),
+    Repeat(Box<Raw>, usize, Option<usize>),
+    And(Box<Raw>),
+    Not(Box<Raw>),
+}
+
+impl Raw {
+    fn sequence(first: Raw, rest: Vec<Raw>) -> Self {
+        match rest.is_empty() {
+            true => first,
+            false => Raw::Sequence([vec![first], rest].concat()),
+        }
+    }
+
+    fn choice(first: Raw, rest: Vec<Raw>) -> Self {
+        match rest.is_empty() {
+            true => first,
+            false => Raw::Choice([vec![first], rest].concat()),
+        }
+    }
+
+    fn suffixed(self, suffix: Option<char>) -> Self {
+        match suffix {
+            Some('*') => Raw::Repeat(Box::new(self), 0, None),
+            Some('+') => Raw::Repeat(Box::new(self), 1, None),
+            Some('?') => Raw::Repeat(Box::new(self), 0, Some(1)),
+            _ => self,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RawRule {
+    name: Spanned<String>,
+    body: Raw,
+}
+
+fn trivia() -> Whitespace {
+    TokenParser::new()
+        .line_comment("#")
+        .block_comment("/*", "*/")
+        .whitespace()
+}
+
+/// The name of a rule that is used inside of an expression, which is not followed by an
+/// arrow (that would be the start of the next rule)
+#[derive(Debug, Clone, Copy)]
+struct Reference;
+
+impl Parser for Reference {
+    type Output = Spanned<String>;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (name, rest) = identifier().spanned().parse(input)?;
+        let ((), after) = trivia().parse(&rest)?;
+        if Arrow.parse(&after).is_ok() {
+            let kind = ParsingErrorKind::Expected("an expression".to_string());
+            return Err(ParsingError::new(kind, input.line, input.col));
+        }
+        Ok((name, rest))
+    }
+}
+
+/// The text of a character class, such as `[a-z_]`
+#[derive(Debug, Clone, Copy)]
+struct ClassText;
+
+impl Parser for ClassText {
+    type Output = Spanned<String>;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let (_, mut rest) = ParseMatch("[").parse(input)?;
+        loop {
+            match rest.source.chars().next() {
+                Some(']') => break,
+                Some('\\') => rest = rest.char_offset(2),
+                Some(_) => rest = rest.char_offset(1),
+                None => {
+                    let kind = ParsingErrorKind::PatternNotFound(
+                        "Did not find the end of the character class ]".to_string(),
+                    );
+                    return Err(ParsingError::new(kind, rest.line, rest.col));
+                }
+            }
+        }
+        let rest = rest.char_offset(1);
+        let text = input.consumed(&rest).unwrap_or_default();
+        let span = Span::new(input.position(), rest.position());
+        Ok((Spanned::new(text, span), rest))
+    }
+}
+
+crate::grammar! {
+    skip = trivia();
+
+    Definition -> RawRule = name: { identifier().spanned() } Arrow body: Choice ";"? => {
+        RawRule { name, body }
+    };
+    Arrow -> String = "<-" | "::=" | "=";
+    Choice -> Raw = first: Sequence rest: (Bar s: Sequence => { s })* => {
+        Raw::choice(first, rest)
+    };
+    Bar -> String = "/" | "|";
+    // The first item is not a part of the repetition, so that its error is not lost when
+    // it fails part of the way through
+    Sequence -> Raw = first: Item ","? rest: (item: Item ","? => { item })* => {
+        Raw::sequence(first, rest)
+    };
+    Item -> Raw = "&" e: Term => { Raw::And(Box::new(e)) }
+        | "!" e: Term => { Raw::Not(Box::new(e)) }
+        | Term;
+    Term -> Raw = e: Atom suffix: { one_of("*+?") }? => { e.suffixed(suffix) };
+    Atom -> Raw = "(" e: Choice ")" => { e }
+        | lit: { StringParser::new().allow_newlines(false) } => { Raw::Literal(lit) }
+        | lit: { StringParser::new().quote('\'').allow_newlines(false) } => { Raw::Literal(lit) }
+        | class: { ClassText } => { Raw::Class(class) }
+        | "." => { Raw::Any }
+        | name: { Reference } => { Raw::Reference(name) };
+}
+
+/// A set of characters, such as `[a-z_]` or `[^"]`
+#[derive(Debug, Clone)]
+struct Class {
+    text: String,
+    negated: bool,
+    ranges: Vec<(char, char)>,
+}
+
+impl Class {
+    fn new(text: &Spanned<String>) -> Result<Self, GrammarError> {
+        let inner = &text.node[1..text.node.len() - 1];
+        let (negated, inner) = match inner.strip_prefix('^') {
+            Some(inner) => (true, inner),
+            None => (false, inner),
+        };
+
+        let mut chars = vec![];
+        let mut escaped = inner.chars();
+        while let Some(c) = escaped.next() {
+            // A `-` that is not escaped makes a range
+            let (c, dash) = match c {
+                '\\' => match escaped.next() {
+                    Some('n') => ('\n', false),
+                    Some('r') => ('\r', false),
+                    Some('t') => ('\t', false),
+                    Some(c) => (c, false),
+                    None => ('\\', false),
+                },
+                c => (c, c == '-'),
+            };
+            chars.push((c, dash));
+        }
+
+        let mut ranges = vec![];
+        let mut i = 0;
+        while i < chars.len() {
+            let (start, _) = chars[i];
+            match (chars.get(i + 1), chars.get(i + 2)) {
+                (Some((_, true)), Some(&(end, _))) => {
+                    if start > end {
+                        let kind = GrammarErrorKind::InvalidRange(format!("{start}-{end}"));
+                        return Err(GrammarError::at(kind, text.span));
+                    }
+                    ranges.push((start, end));
+                    i += 3;
+                }
+                _ => {
+                    ranges.push((start, start));
+                    i += 1;
+                }
+            }
+        }
+
+        Ok(Class {
+            text: text.node.clone(),
+            negated,
+            ranges,
+        })
+    }
+
+    fn contains(&self, c: char) -> bool {
+        let found = self
+            .ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&c));
+        found != self.negated
+    }
+}
+
+/// The expressions of a grammar, with references resolved to rule indices
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(String),
+    Class(Class),
+    Any,
+    Rule(usize),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Repeat {
+        item: Box<Expr>,
+        min: usize,
+        max: Option<usize>,
+    },
+    And(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Expr {
+    fn resolve(raw: &Raw, names: &HashMap<String, usize>) -> Result<Self, GrammarError> {
+        let all = |items: &[Raw]| {
+            items
+                .iter()
+                .map(|item| Expr::resolve(item, names))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let boxed = |item: &Raw| Expr::resolve(item, names).map(Box::new);
+
+        Ok(match raw {
+            Raw::Literal(lit) => Expr::Literal(lit.clone()),
+            Raw::Class(text) => Expr::Class(Class::new(text)?),
+            Raw::Any => Expr::Any,
+            Raw::Reference(name) => match names.get(&name.node) {
+                Some(index) => Expr::Rule(*index),
+                None => {
+                    let kind = GrammarErrorKind::UndefinedRule(name.node.clone());
+                    return Err(GrammarError::at(kind, name.span));
+                }
+            },
+            Raw::Sequence(items) => Expr::Sequence(all(items)?),
+            Raw::Choice(options) => Expr::Choice(all(options)?),
+            Raw::Repeat(item, min, max) => Expr::Repeat {
+                item: boxed(item)?,
+                min: *min,
+                max: *max,
+            },
+            Raw::And(item) => Expr::And(boxed(item)?),
+            Raw::Not(item) => Expr::Not(boxed(item)?),
+        })
+    }
+
+    /// The expression can match without consuming anything
+    fn nullable(&self, rules: &[bool]) -> bool {
+        match self {
+            Expr::Literal(lit) => lit.is_empty(),
+            Expr::Class(_) | Expr::Any => false,
+            Expr::Rule(index) => rules[*index],
+            Expr::Sequence(items) => items.iter().all(|item| item.nullable(rules)),
+            Expr::Choice(options) => options.iter().any(|option| option.nullable(rules)),
+            Expr::Repeat { item, min, .. } => *min == 0 || item.nullable(rules),
+            Expr::And(_) | Expr::Not(_) => true,
+        }
+    }
+
+    /// The rules that the expression may call before consuming anything
+    fn left_calls(&self, nullable: &[bool], calls: &mut Vec<usize>) {
+        match self {
+            Expr::Literal(_) | Expr::Class(_) | Expr::Any => {}
+            Expr::Rule(index) => calls.push(*index),
+            Expr::Sequence(items) => {
+                for item in items {
+                    item.left_calls(nullable, calls);
+                    if !item.nullable(nullable) {
+                        break;
+                    }
+                }
+            }
+            Expr::Choice(options) => {
+                for option in options {
+                    option.left_calls(nullable, calls);
+                }
+            }
+            Expr::Repeat { item, .. } | Expr::And(item) | Expr::Not(item) => {
+                item.left_calls(nullable, calls)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    name: String,
+    /// Where the rule is defined in the grammar
+    span: Span,
+    body: Expr,
+}
+
+impl Rule {
+    /// Silent rules, whose names start with `_`, do not get a node of their own. Whatever
+    /// they match is added to the node of the rule that called them.
+    fn is_silent(&self) -> bool {
+        self.name.starts_with('_')
+    }
+}
+
+/// A parsing expression grammar that is loaded from text at runtime, and that parses
+/// source into a tree of `Node`s.
+///
+/// A grammar is a list of rules, `Name <- expression`, where `=` or `::=` may be used
+/// instead of `<-`, and each rule may end with a `;`. The first rule is where parsing
+/// starts. Expressions are made of:
+///
+/// - literals, `"if"` or `'if'`, character classes, `[a-z_]` or `[^"]`, and `.` for
+///   any character
+/// - references to other rules, by name
+/// - sequences, `a b c` (or `a, b, c`), and ordered choices, `a / b` (or `a | b`)
+/// - repetitions, `a*`, `a+` and `a?`, and lookaheads, `&a` and `!a`, which do not
+///   consume anything
+/// - groups, `(a b)`
+///
+/// Comments start with `#` and last until the end of the line, or are between `/*` and
+/// `*/`. Whitespace in the source is not skipped unless the grammar says so, so it is
+/// common to have a silent rule for it: rules whose names start with `_` do not get a node
+/// in the tree.
+///
+/// Errors in the grammar, such as references to rules that do not exist, or rules that
+/// are left recursive, are reported with the line and column where they are.
+///
+/// Rules may be nested up to `max_depth` levels while parsing, so that deeply nested
+/// input gives an error rather than overflowing the stack.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::peg::{GrammarErrorKind, Peg};
+///
+/// let peg = Peg::new(
+///     r#"
+///     Sum     <- Product (_ "+" _ Product)*
+///     Product <- Number (_ "*" _ Number)*
+///     Number  <- [0-9]+
+///     _       <- [ \t]*
+///     "#,
+/// )
+/// .unwrap();
+///
+/// let source = "1 + 2*3";
+/// let tree = peg.parse(source).unwrap();
+/// assert_eq!(tree.rule, "Sum");
+/// let products = tree.children.iter().map(|n| n.text(source)).collect::<Vec<_>>();
+/// assert_eq!(products, vec!["1", "2*3"]);
+///
+/// let err = Peg::new("Sum <- Product\nProduct <- Numbr").unwrap_err();
+/// assert_eq!(err.kind(), &GrammarErrorKind::UndefinedRule("Numbr".to_string()));
+/// assert_eq!((err.line(), err.col()), (1, 11));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Peg {
+    rules: Vec<Rule>,
+    max_depth: usize,
+}
+
+impl Peg {
+    /// Load a grammar from its text
+    pub fn new(grammar: &str) -> Result<Self, GrammarError> {
+        let raw = parse_rules(grammar)?;
+        if raw.is_empty() {
+            return Err(GrammarError::new(GrammarErrorKind::NoRules, 0, 0));
+        }
+
+        let mut names = HashMap::new();
+        for (index, rule) in raw.iter().enumerate() {
+            if names.insert(rule.name.node.clone(), index).is_some() {
+                let kind = GrammarErrorKind::DuplicateRule(rule.name.node.clone());
+                return Err(GrammarError::at(kind, rule.name.span));
+            }
+        }
+
+        let rules = raw
+            .iter()
+            .map(|rule| {
+                Ok(Rule {
+                    name: rule.name.node.clone(),
+                    span: rule.name.span,
+                    body: Expr::resolve(&rule.body, &names)?,
+                })
+            })
+            .collect::<Result<Vec<_>, GrammarError>>()?;
+
+        let peg = Peg {
+            rules,
+            max_depth: 128,
+        };
+        peg.check_left_recursion()?;
+        Ok(peg)
+    }
+
+    fn check_left_recursion(&self) -> Result<(), GrammarError> {
+        let mut nullable = vec![false; self.rules.len()];
+        loop {
+            let next = self
+                .rules
+                .iter()
+                .map(|rule| rule.body.nullable(&nullable))
+                .collect::<Vec<_>>();
+            if next == nullable {
+                break;
+            }
+            nullable = next;
+        }
+
+        let calls = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let mut calls = vec![];
+                rule.body.left_calls(&nullable, &mut calls);
+                calls
+            })
+            .collect::<Vec<_>>();
+
+        for start in 0..self.rules.len() {
+            // Look for a path of left calls that leads back to `start`
+            let mut path = vec![start];
+            let mut seen = vec![false; self.rules.len()];
+            if self.find_cycle(start, &calls, &mut path, &mut seen) {
+                let names = path.iter().map(|i| self.rules[*i].name.clone()).collect();
+                let kind = GrammarErrorKind::LeftRecursion(names);
+                return Err(GrammarError::at(kind, self.rules[start].span));
+            }
+        }
+        Ok(())
+    }
+
+    fn find_cycle(
+        &self,
+        start: usize,
+        calls: &[Vec<usize>],
+        path: &mut Vec<usize>,
+        seen: &mut [bool],
+    ) -> bool {
+        let current = *path.last().expect("the path starts with a rule");
+        for &next in &calls[current] {
+            if next == start {
+                path.push(next);
+                return true;
+            }
+            if !seen[next] {
+                seen[next] = true;
+                path.push(next);
+                if self.find_cycle(start, calls, path, seen) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    /// How many rules may be nested inside of each other while parsing (128 by default)
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// The names of the rules, in the order that they were defined
+    pub fn rules(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|rule| rule.name.as_str())
+    }
+
+    /// A parser for one of the rules of the grammar
+    pub fn rule(&self, name: &str) -> Option<RuleParser<'_>> {
+        let index = self.rules.iter().position(|rule| rule.name == name)?;
+        Some(RuleParser { peg: self, index })
+    }
+
+    /// A parser for the first rule of the grammar
+    pub fn start(&self) -> RuleParser<'_> {
+        RuleParser {
+            peg: self,
+            index: 0,
+        }
+    }
+
+    /// Parse all of some source with the first rule. If the rule stops before the end of
+    /// the source, the error is the one that got the furthest.
+    pub fn parse(&self, source: &str) -> Result<Node, ParsingError> {
+        Run::new(self, source, Position::default(), false).parse()
+    }
+
+    /// Parse all of some source like `Peg::parse`, remembering what every rule matched
+    /// wherever it was tried, so that the source can be edited and parsed again with
+    /// `Peg::reparse`
+    pub fn parse_incremental(&self, source: &str) -> IncrementalParse {
+        self.run_incremental(source.to_string(), Memo::new(), vec![])
+    }
+
+    /// Apply an edit to the source of a previous parse, and parse it again.
+    ///
+    /// Only the rules that looked at the edited part of the source are parsed again, what
+    /// the other rules matched is reused, so the result is the same as parsing all of the
+    /// new source, without matching the other rules again. The nodes that come after the
+    /// edit are still moved to their new positions. The previous parse must have been made
+    /// by this grammar.
+    ///
+    /// Panics if the edit does not start and end on character boundaries of the source.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use parlib::peg::{Edit, Peg};
+    ///
+    /// let peg = Peg::new("List <- Item (\",\" Item)*\nItem <- [a-z]+").unwrap();
+    /// let parsed = peg.parse_incremental("ab,cd,ef");
+    /// let parsed = peg.reparse(parsed, &Edit::new(3, 5, "xyz"));
+    ///
+    /// assert_eq!(parsed.source(), "ab,xyz,ef");
+    /// assert_eq!(parsed.result(), peg.parse("ab,xyz,ef").as_ref());
+    /// // `ab` and `ef` were not parsed again
+    /// assert_eq!(parsed.reused(), 2);
+    /// ```
+    pub fn reparse(&self, previous: IncrementalParse, edit: &Edit) -> IncrementalParse {
+        let IncrementalParse {
+            mut source,
+            memo,
+            mut shifts,
+            ..
+        } = previous;
+        let old_end = position_of(&source, edit.end);
+        source.replace_range(edit.start..edit.end, &edit.text);
+        let shift = Shift {
+            start: edit.start,
+            old_end,
+            new_end: position_of(&source, edit.start + edit.text.len()),
+        };
+
+        // What a rule matched is still right if it did not look at the edited bytes, and
+        // it only needs to be moved if it comes after them. Only the offset of an entry
+        // is moved here, its result is moved once it is used, see `Entry::settle`.
+        let mut kept = Memo::with_capacity(memo.len());
+        kept.extend(memo.into_iter().filter_map(|((index, offset), mut entry)| {
+            entry.kept = true;
+            if offset + entry.examined <= edit.start {
+                Some(((index, offset), entry))
+            } else if offset >= edit.end {
+                Some(((index, shift.offset(offset)), entry))
+            } else {
+                None
+            }
+        }));
+        shifts.push(shift);
+        self.run_incremental(source, kept, shifts)
+    }
+
+    fn run_incremental(&self, source: String, memo: Memo, shifts: Vec<Shift>) -> IncrementalParse {
+        let mut run = Run::with_memo(self, &source, memo, &shifts);
+        let result = run.parse();
+        let (memo, reused) = (run.memo.unwrap_or_default(), run.reused);
+        IncrementalParse {
+            source,
+            result,
+            memo,
+            shifts,
+            reused,
+        }
+    }
+
+    /// Describe the rules of the grammar, to render them as EBNF or as diagrams
+    pub fn describe(&self) -> Grammar {
+        self.rules.iter().fold(Grammar::new(), |grammar, rule| {
+            grammar.rule(&rule.name, self.describe_expr(&rule.body))
+        })
+    }
+
+    fn describe_expr(&self, expr: &Expr) -> Description {
+        match expr {
+            Expr::Literal(lit) => Description::Literal(lit.clone()),
+            Expr::Class(class) => Description::Class(class.text.clone()),
+            Expr::Any => Description::Class("any character".to_string()),
+            Expr::Rule(index) => Description::Reference(self.rules[*index].name.clone()),
+            Expr::Sequence(items) => items
+                .iter()
+                .map(|item| self.describe_expr(item))
+                .fold(Description::Empty, Description::sequence),
+            Expr::Choice(options) => {
+                Description::Choice(options.iter().map(|o| self.describe_expr(o)).collect())
+            }
+            Expr::Repeat { item, min, max } => {
+                Description::repeat(self.describe_expr(item), *min, *max)
+            }
+            // Lookaheads do not consume anything
+            Expr::And(_) | Expr::Not(_) => Description::Empty,
+        }
+    }
+}
+
+/// A change to some source: the bytes from `start` up to `end` are replaced by `text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(start: usize, end: usize, text: &str) -> Self {
+        Edit {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+}
+
+/// A parse that remembers what the rules matched, to be edited with `Peg::reparse`
+#[derive(Debug, Clone)]
+pub struct IncrementalParse {
+    source: String,
+    result: Result<Node, ParsingError>,
+    memo: Memo,
+    /// Every edit since the first parse, as the entries of the memo may not have been
+    /// moved past them yet
+    shifts: Vec<Shift>,
+    reused: usize,
+}
+
+impl IncrementalParse {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The tree of the source, or the error that got the furthest, as `Peg::parse` gives
+    pub fn result(&self) -> Result<&Node, &ParsingError> {
+        self.result.as_ref()
+    }
+
+    /// How many of the matches (and failures) of rules from before the last edit were
+    /// used again by this parse
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+}
+
+/// Moves the positions that come after an edit to where they are after it
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    /// The start of the edit, which is the same in both sources
+    start: usize,
+    /// The end of the edit, in the old source
+    old_end: Position,
+    /// The end of the edit, in the new source
+    new_end: Position,
+}
+
+impl Shift {
+    fn offset(&self, offset: usize) -> usize {
+        offset - self.old_end.offset + self.new_end.offset
+    }
+
+    fn line_col(&self, line: usize, col: usize) -> (usize, usize) {
+        match line == self.old_end.line {
+            true => (self.new_end.line, col - self.old_end.col + self.new_end.col),
+            false => (line - self.old_end.line + self.new_end.line, col),
+        }
+    }
+
+    fn position(&self, position: Position) -> Position {
+        let (line, col) = self.line_col(position.line, position.col);
+        Position {
+            line,
+            col,
+            offset: self.offset(position.offset),
+        }
+    }
+
+    fn error(&self, error: ParsingError) -> ParsingError {
+        let (line, col) = self.line_col(error.line(), error.col());
+        ParsingError::new(error.kind().clone(), line, col)
+    }
+}
+
+/// Move a node, and the nodes inside of it, past every edit of `shifts` in turn
+fn shift_node(node: &Node, shifts: &[Shift]) -> Arc<Node> {
+    let position = |p| shifts.iter().fold(p, |p, shift| shift.position(p));
+    Arc::new(Node {
+        rule: node.rule.clone(),
+        span: Span::new(position(node.span.start), position(node.span.end)),
+        children: node
+            .children
+            .iter()
+            .map(|n| shift_node(n, shifts))
+            .collect(),
+    })
+}
+
+/// The position of a byte offset in some source
+fn position_of(source: &str, offset: usize) -> Position {
+    advance(Position::default(), &source[..offset])
+}
+
+/// The position after some text that starts at `position`
+fn advance(position: Position, text: &str) -> Position {
+    text.chars().fold(position, step)
+}
+
+/// The position after a character, counting lines as `Input::char_offset` does
+fn step(position: Position, c: char) -> Position {
+    let offset = position.offset + c.len_utf8();
+    match c {
+        '\n' => Position {
+            line: position.line + 1,
+            col: 0,
+            offset,
+        },
+        _ => Position {
+            col: position.col + 1,
+            offset,
+            ..position
+        },
+    }
+}
+
+/// The rest of an input, from a position further into it
+fn move_to(input: &Input, position: Position) -> Input {
+    let mut rest = input.clone();
+    rest.source.drain(..position.offset - input.offset);
+    rest.line = position.line;
+    rest.col = position.col;
+    rest.offset = position.offset;
+    rest
+}
+
+/// Parse the rules of a grammar, without resolving references
+fn parse_rules(grammar: &str) -> Result<Vec<RawRule>, GrammarError> {
+    let mut rules = vec![];
+    let mut rest = Input::from(grammar);
+    loop {
+        let ((), next) = trivia().parse(&rest)?;
+        if next.is_at_end() {
+            return Ok(rules);
+        }
+
+        // The body of a rule ends at the first thing that is not a part of an expression,
+        // which should be the start of the next rule. If it is not, that thing is the
+        // error, rather than a rule that is missing its arrow.
+        if !rules.is_empty()
+            && identifier()
+                .and_then(trivia())
+                .and_then(Arrow)
+                .parse(&next)
+                .is_err()
+        {
+            let err = match Item.parse(&next) {
+                Err(err) => err,
+                Ok(_) => {
+                    let kind = ParsingErrorKind::Expected("Definition".to_string());
+                    ParsingError::new(kind, next.line, next.col)
+                }
+            };
+            return Err(err.into());
+        }
+
+        let (rule, next) = Definition.parse(&next)?;
+        rules.push(rule);
+        rest = next;
+    }
+}
+
+/// A parser for one rule of a `Peg`, which outputs the tree of nodes that it matched
+#[derive(Debug, Clone, Copy)]
+pub struct RuleParser<'g> {
+    peg: &'g Peg,
+    index: usize,
+}
+
+impl Parser for RuleParser<'_> {
+    type Output = Node;
+    fn parse(&self, input: &Input) -> ParserRes<Self::Output> {
+        let mut run = Run::new(
+            self.peg,
+            &input.source,
+            input.position(),
+            input.is_partial(),
+        );
+        let parsed = run.rule(self.index, input.position());
+        let (node, end) = run.finish(parsed)?;
+        Ok((Arc::unwrap_or_clone(node), move_to(input, end)))
+    }
+
+    fn describe(&self) -> Description {
+        let rule = &self.peg.rules[self.index];
+        Description::rule(&rule.name, self.peg.describe_expr(&rule.body))
+    }
+}
+
+/// The error that got the furthest in a run, and whether it is a rule that named it
+#[derive(Debug, Clone)]
+struct Furthest {
+    error: ParsingError,
+    named: bool,
+}
+
+/// What a rule matched at some offset, or the error if it failed, as remembered by an
+/// `IncrementalParse`
+#[derive(Debug, Clone)]
+struct Entry {
+    result: Matched,
+    /// The offset that the rule was parsed at, as of the last time the result was moved
+    start: usize,
+    /// How many bytes from the start were looked at to get the result
+    examined: usize,
+    /// The error that got the furthest while the rule was being parsed
+    furthest: Option<Furthest>,
+    /// Whether the entry was kept from before the last edit, and has not been used since
+    kept: bool,
+    /// How many of the edits of the parse the result has been moved past, or was before
+    moved: usize,
+}
+
+impl Entry {
+    /// Move the result and the error of the entry past the edits that came before it.
+    /// This waits until the entry is used, as moving a result means moving every node in
+    /// it, and most of the entries after an edit are never used again.
+    fn settle(&mut self, shifts: &[Shift]) {
+        let mut start = self.start;
+        let pending = &shifts[self.moved..];
+        self.moved = shifts.len();
+        let shifts = pending
+            .iter()
+            .filter(|shift| {
+                // An entry that was kept through an edit was either before it or after it
+                let after = start + self.examined > shift.start;
+                if after {
+                    start = shift.offset(start);
+                }
+                after
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        if shifts.is_empty() {
+            return;
+        }
+
+        self.start = start;
+        let error = |e| shifts.iter().fold(e, |e, shift| shift.error(e));
+        self.result = match &self.result {
+            Ok((node, end)) => {
+                let end = shifts.iter().fold(*end, |end, shift| shift.position(end));
+                Ok((shift_node(node, &shifts), end))
+            }
+            Err(e) => Err(error(e.clone())),
+        };
+        if let Some(furthest) = &mut self.furthest {
+            furthest.error = error(furthest.error.clone());
+        }
+    }
+}
+
+/// The entries of an `IncrementalParse`, by rule and by offset
+type Memo = HashMap<(usize, usize), Entry>;
+
+/// The node that a rule matched and where it ended, or the error if it failed
+type Matched = Result<(Arc<Node>, Position), ParsingError>;
+
+/// One run of a grammar over some source, which keeps the error that got the furthest.
+///
+/// The run works on positions in the source rather than on `Input`s, so that moving
+/// through the source, or to the end of a remembered match, does not copy it.
+struct Run<'g, 's> {
+    peg: &'g Peg,
+    /// The source that is parsed, which starts at `start`
+    source: &'s str,
+    start: Position,
+    /// Whether more of the source may come later, as with `Input::partial`
+    partial: bool,
+    furthest: Option<Furthest>,
+    /// What the rules matched, if the run remembers it
+    memo: Option<Memo>,
+    /// The edits that the entries of the memo may need to be moved past
+    shifts: &'s [Shift],
+    /// The end of the bytes that were looked at by the rule that is being parsed
+    examined: usize,
+    /// How many entries of the memo were kept from before an edit, and then used
+    reused: usize,
+    /// How many rules are being parsed inside of each other
+    depth: usize,
+    /// The error once the rules are nested too deeply, which ends the run
+    too_deep: Option<ParsingError>,
+}
+
+impl<'g, 's> Run<'g, 's> {
+    fn new(peg: &'g Peg, source: &'s str, start: Position, partial: bool) -> Self {
+        Run {
+            peg,
+            source,
+            start,
+            partial,
+            furthest: None,
+            memo: None,
+            shifts: &[],
+            examined: 0,
+            reused: 0,
+            depth: 0,
+            too_deep: None,
+        }
+    }
+
+    fn with_memo(peg: &'g Peg, source: &'s str, memo: Memo, shifts: &'s [Shift]) -> Self {
+        Run {
+            memo: Some(memo),
+            shifts,
+            ..Run::new(peg, source, Position::default(), false)
+        }
+    }
+
+    /// The rest of the source, from a position in it
+    fn rest(&self, at: Position) -> &'s str {
+        &self.source[at.offset - self.start.offset..]
+    }
+
+    /// Parse all of the source with the first rule
+    fn parse(&mut self) -> Result<Node, ParsingError> {
+        let parsed = self.rule(0, self.start);
+        let (node, end) = self.finish(parsed)?;
+        if !self.rest(end).is_empty() {
+            let kind = ParsingErrorKind::Expected("the end of the input".to_string());
+            return Err(self.furthest_or(ParsingError::new(kind, end.line, end.col)));
+        }
+        Ok(Arc::unwrap_or_clone(node))
+    }
+
+    /// The result of parsing a rule at the top of the run. Nesting the rules too deeply
+    /// is an error, even if a lookahead or a repetition would have hidden it.
+    fn finish(&mut self, parsed: Matched) -> Matched {
+        if let Some(error) = self.too_deep.take() {
+            return Err(error);
+        }
+        parsed.map_err(|e| self.furthest_or(e))
+    }
+
+    fn fail(&mut self, kind: ParsingErrorKind, at: Position) -> ParsingError {
+        let error = ParsingError::new(kind, at.line, at.col);
+        self.keep(error.clone(), false);
+        error
+    }
+
+    /// Keep an error if it got the furthest. The outermost rule that failed at the
+    /// furthest position names the error, so a named error also replaces one that got
+    /// just as far.
+    fn keep(&mut self, error: ParsingError, named: bool) {
+        let replace = match &self.furthest {
+            None => true,
+            Some(furthest) if named => error >= furthest.error,
+            Some(furthest) => error > furthest.error,
+        };
+        if replace {
+            self.furthest = Some(Furthest { error, named });
+        }
+    }
+
+    fn furthest_or(&self, error: ParsingError) -> ParsingError {
+        match &self.furthest {
+            Some(furthest) if furthest.error > error => furthest.error.clone(),
+            _ => error,
+        }
+    }
+
+    fn examine(&mut self, end: usize) {
+        self.examined = self.examined.max(end);
+    }
+
+    fn rule(&mut self, index: usize, at: Position) -> Matched {
+        if self.too_deep.is_some() || self.depth == self.peg.max_depth {
+            return Err(self.nested_too_deeply(at));
+        }
+
+        self.depth += 1;
+        let parsed = self.remembered_rule(index, at);
+        self.depth -= 1;
+        parsed
+    }
+
+    #[cold]
+    fn nested_too_deeply(&mut self, at: Position) -> ParsingError {
+        let max_depth = self.peg.max_depth;
+        self.too_deep
+            .get_or_insert_with(|| {
+                let kind = ParsingErrorKind::PatternNotFound(format!(
+                    "rules are nested more than {max_depth} levels deep"
+                ));
+                ParsingError::new(kind, at.line, at.col)
+            })
+            .clone()
+    }
+
+    /// Parse a rule, or remember what it parsed before if the run has a memo
+    fn remembered_rule(&mut self, index: usize, at: Position) -> Matched {
+        if self.memo.is_none() {
+            return self.match_rule(index, at);
+        }
+
+        let key = (index, at.offset);
+        if let Some(entry) = self.memo.as_mut().and_then(|memo| memo.get_mut(&key)) {
+            if entry.kept {
+                entry.kept = false;
+                self.reused += 1;
+            }
+            entry.settle(self.shifts);
+            let (result, examined) = (entry.result.clone(), at.offset + entry.examined);
+            let furthest = entry.furthest.clone();
+            self.examine(examined);
+            // Keeping the furthest error of the rule now gives the same error as keeping
+            // each of the errors that it kept when it was parsed
+            if let Some(furthest) = furthest {
+                self.keep(furthest.error, furthest.named);
+            }
+            return result;
+        }
+
+        // Find what the rule looks at, and the error that gets the furthest, on their own
+        let outer_examined = std::mem::replace(&mut self.examined, at.offset);
+        let outer_furthest = self.furthest.take();
+        let result = self.match_rule(index, at);
+        // A rule that was cut off by nesting too deeply did not get a result to remember
+        if self.too_deep.is_none() && !matches!(&result, Err(e) if e.is_incomplete()) {
+            let entry = Entry {
+                result: result.clone(),
+                start: at.offset,
+                examined: self.examined - at.offset,
+                furthest: self.furthest.clone(),
+                kept: false,
+                moved: self.shifts.len(),
+            };
+            if let Some(memo) = &mut self.memo {
+                memo.insert(key, entry);
+            }
+        }
+
+        self.examine(outer_examined);
+        let inner_furthest = std::mem::replace(&mut self.furthest, outer_furthest);
+        if let Some(furthest) = inner_furthest {
+            self.keep(furthest.error, furthest.named);
+        }
+        result
+    }
+
+    fn match_rule(&mut self, index: usize, at: Position) -> Matched {
+        let peg = self.peg;
+        let rule = &peg.rules[index];
+        let mut children = vec![];
+        match self.eval(&rule.body, at, &mut children) {
+            Ok(end) => {
+                let node = Node {
+                    rule: rule.name.clone(),
+                    span: Span::new(at, end),
+                    children,
+                };
+                Ok((Arc::new(node), end))
+            }
+            Err(e) if e.is_incomplete() => Err(e),
+            Err(e) => {
+                // An error that got no further than the rule is named after the rule
+                let error = match (e.line(), e.col()) == (at.line, at.col) {
+                    true => {
+                        let kind = ParsingErrorKind::Expected(rule.name.clone());
+                        ParsingError::new(kind, at.line, at.col)
+                    }
+                    false => e,
+                };
+                self.keep(error.clone(), true);
+                Err(error)
+            }
+        }
+    }
+
+    /// Match an expression, adding the nodes of the rules that it matched to `children`.
+    ///
+    /// Each kind of expression is matched by a method of its own, which keeps the stack
+    /// frames of this recursion small.
+    fn eval(
+        &mut self,
+        expr: &Expr,
+        at: Position,
+        children: &mut Vec<Arc<Node>>,
+    ) -> Result<Position, ParsingError> {
+        match expr {
+            Expr::Literal(lit) => self.literal(lit, at),
+            Expr::Class(class) => self.char(at, &class.text, |c| class.contains(c)),
+            Expr::Any => self.char(at, "any character", |_| true),
+            Expr::Rule(index) => self.reference(*index, at, children),
+            Expr::Sequence(items) => self.sequence(items, at, children),
+            Expr::Choice(options) => self.choice(options, at, children),
+            Expr::Repeat { item, min, max } => self.repeat(item, *min, *max, at, children),
+            Expr::And(item) => {
+                self.eval(item, at, &mut vec![])?;
+                Ok(at)
+            }
+            Expr::Not(item) => self.not(item, at),
+        }
+    }
+
+    fn literal(&mut self, lit: &str, at: Position) -> Result<Position, ParsingError> {
+        self.examine(at.offset + lit.len());
+        let rest = self.rest(at);
+        if rest.starts_with(lit) {
+            return Ok(advance(at, lit));
+        }
+        if self.partial && lit.starts_with(rest) {
+            let kind = ParsingErrorKind::Incomplete(Needed::Size(lit.len() - rest.len()));
+            return Err(ParsingError::new(kind, at.line, at.col));
+        }
+        Err(self.fail(ParsingErrorKind::Expected(format!("{lit:?}")), at))
+    }
+
+    fn reference(
+        &mut self,
+        index: usize,
+        at: Position,
+        children: &mut Vec<Arc<Node>>,
+    ) -> Result<Position, ParsingError> {
+        let (node, end) = self.rule(index, at)?;
+        match self.peg.rules[index].is_silent() {
+            true => children.extend(node.children.iter().cloned()),
+            false => children.push(node),
+        }
+        Ok(end)
+    }
+
+    fn sequence(
+        &mut self,
+        items: &[Expr],
+        at: Position,
+        children: &mut Vec<Arc<Node>>,
+    ) -> Result<Position, ParsingError> {
+        let len = children.len();
+        let mut rest = at;
+        for item in items {
+            match self.eval(item, rest, children) {
+                Ok(next) => rest = next,
+                Err(e) => {
+                    children.truncate(len);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(rest)
+    }
+
+    fn choice(
+        &mut self,
+        options: &[Expr],
+        at: Position,
+        children: &mut Vec<Arc<Node>>,
+    ) -> Result<Position, ParsingError> {
+        let mut best = None;
+        for option in options {
+            let len = children.len();
+            match self.eval(option, at, children) {
+                Ok(end) => return Ok(end),
+                Err(e) if e.is_incomplete() => return Err(e),
+                Err(e) => {
+                    children.truncate(len);
+                    keep_furthest(&mut best, e);
+                }
+            }
+        }
+        Err(best.expect("a choice has at least two options"))
+    }
+
+    fn repeat(
+        &mut self,
+        item: &Expr,
+        min: usize,
+        max: Option<usize>,
+        at: Position,
+        children: &mut Vec<Arc<Node>>,
+    ) -> Result<Position, ParsingError> {
+        let mut rest = at;
+        let mut count = 0;
+        while max.is_none_or(|max| count < max) {
+            let len = children.len();
+            match self.eval(item, rest, children) {
+                Ok(next) => {
+                    // An item that matches nothing would match forever
+                    let progress = next.offset != rest.offset;
+                    rest = next;
+                    count += 1;
+                    if !progress {
+                        break;
+                    }
+                }
+                Err(e) if e.is_incomplete() || count < min => {
+                    children.truncate(len);
+                    return Err(e);
+                }
+                Err(_) => {
+                    children.truncate(len);
+                    break;
+                }
+            }
+        }
+        Ok(rest)
+    }
+
+    fn not(&mut self, item: &Expr, at: Position) -> Result<Position, ParsingError> {
+        // Failing is what a negative lookahead is hoping for, so it is not an error
+        let furthest = self.furthest.clone();
+        let parsed = self.eval(item, at, &mut vec![]);
+        self.furthest = furthest;
+        match parsed {
+            Ok(_) => {
+                let kind = ParsingErrorKind::PatternNotFound("unexpected input".to_string());
+                Err(self.fail(kind, at))
+            }
+            Err(e) if e.is_incomplete() => Err(e),
+            Err(_) => Ok(at),
+        }
+    }
+
+    fn char(
+        &mut self,
+        at: Position,
+        label: &str,
+        accept: impl Fn(char) -> bool,
+    ) -> Result<Position, ParsingError> {
+        let next = self.rest(at).chars().next();
+        self.examine(at.offset + next.map_or(1, char::len_utf8));
+        match next {
+            Some(c) if accept(c) => Ok(step(at, c)),
+            None if self.partial => {
+                let kind = ParsingErrorKind::Incomplete(Needed::Size(1));
+                Err(ParsingError::new(kind, at.line, at.col))
+            }
+            _ => Err(self.fail(ParsingErrorKind::Expected(label.to_string()), at)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_peg {
+    use std::sync::Arc;
+
+    use super::{Edit, GrammarErrorKind, IncrementalParse, Peg};
+    use crate::{errors::ParsingErrorKind, inputs::Input, traits::Parser};
+
+    const JSON: &str = r#"
+        # A small JSON grammar
+        Value   <- _ (Object / Array / String / Number / Literal) _
+        Object  <- "{" _ (Member (_ "," _ Member)*)? _ "}"
+        Member  <- String _ ":" Value
+        Array   <- "[" (Value ("," Value)*)? _ "]"
+        String  <- '"' ([^"\\] / "\\" .)* '"'
+        Number  <- "-"? [0-9]+ ("." [0-9]+)?
+        Literal <- "true" / "false" / "null"
+        _       <- [ \t\r\n]*
+    "#;
+
+    #[test]
+    fn concrete_syntax_tree() {
+        let peg = Peg::new(JSON).unwrap();
+        let source = r#" {"a": [1, -2.5], "b": null} "#;
+        let tree = peg.parse(source).unwrap();
+        assert_eq!(tree.rule, "Value");
+        assert_eq!(tree.text(source), source);
+
+        let object = &tree.children[0];
+        assert_eq!(object.rule, "Object");
+        assert_eq!(object.text(source), r#"{"a": [1, -2.5], "b": null}"#);
+        assert_eq!(object.span.start.col, 1);
+
+        let members = object
+            .children
+            .iter()
+            .map(|member| member.children[0].text(source))
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![r#""a""#, r#""b""#]);
+
+        let array = &object.children[0].children[1].children[0];
+        assert_eq!(array.rule, "Array");
+        let numbers = array
+            .children
+            .iter()
+            .map(|value| value.children[0].text(source))
+            .collect::<Vec<_>>();
+        assert_eq!(numbers, vec!["1", "-2.5"]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let peg = Peg::new(JSON).unwrap();
+
+        // The error that got the furthest is reported, rather than the end of the input
+        let err = peg.parse("[1, 2,]").unwrap_err();
+        assert_eq!((err.line(), err.col()), (0, 6));
+        assert_eq!(err.kind(), &ParsingErrorKind::Expected("Value".to_string()));
+
+        let err = peg.parse("[1]\n]").unwrap_err();
+        assert_eq!((err.line(), err.col()), (1, 0));
+
+        // Rules can be used as parsers on their own
+        let number = peg.rule("Number").unwrap();
+        let (node, rest) = number.parse(&Input::from("12,")).unwrap();
+        assert_eq!(node.span.len(), 2);
+        assert_eq!(rest.source, ",".to_string());
+        let err = number.parse(&Input::from("x")).unwrap_err();
+        assert_eq!(
+            err.kind(),
+            &ParsingErrorKind::Expected("Number".to_string())
+        );
+        assert!(peg.rule("Nothing").is_none());
+        assert_eq!(peg.rules().count(), 8);
+    }
+
+    #[test]
+    fn lookaheads_and_classes() {
+        let peg = Peg::new(
+            "Words = (Word ' '?)+ ;\n\
+             Word = !Keyword [a-zA-Z\\-]+ ;\n\
+             Keyword = ('if' | 'else') ![a-z] ;",
+        )
+        .unwrap();
+        let tree = peg.parse("iffy well-known elsewhere").unwrap();
+        assert_eq!(tree.children.len(), 3);
+        assert!(peg.parse("iffy else").is_err());
+
+        // Lookaheads leave no nodes behind
+        let peg = Peg::new("A <- &B B\nB <- 'b'").unwrap();
+        assert_eq!(peg.parse("b").unwrap().children.len(), 1);
+    }
+
+    #[test]
+    fn nesting_depth() {
+        let peg = Peg::new("V <- '[' V? ']' / 'x'").unwrap();
+        let nested = |depth: usize| format!("{}x{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(peg.parse(&nested(120)).is_ok());
+
+        // Deeper input is an error, rather than a stack overflow
+        for source in [nested(100_000), "[".repeat(100_000)] {
+            let err = peg.parse(&source).unwrap_err();
+            assert!(matches!(err.kind(), ParsingErrorKind::PatternNotFound(_)));
+            assert_eq!(err.col(), 128);
+            assert!(peg.parse_incremental(&source).result().is_err());
+        }
+
+        let shallow = peg.clone().max_depth(3);
+        assert!(shallow.parse(&nested(2)).is_ok());
+        assert_eq!(shallow.parse(&nested(3)).unwrap_err().col(), 3);
+
+        // A lookahead does not hide that the rules were nested too deeply
+        let peg = Peg::new("S <- !V '['*\nV <- '[' V ']'")
+            .unwrap()
+            .max_depth(3);
+        assert!(peg.parse("[[[[[[").is_err());
+    }
+
+    #[test]
+    fn grammar_errors() {
+        let at = |grammar: &str| {
+            let err = Peg::new(grammar).unwrap_err();
+            (err.kind().clone(), err.line(), err.col())
+        };
+
+        assert_eq!(at("  # nothing\n"), (GrammarErrorKind::NoRules, 0, 0));
+        assert_eq!(
+            at("A <- 'a'\nB <- 'b'\nA <- 'c'"),
+            (GrammarErrorKind::DuplicateRule("A".to_string()), 2, 0)
+        );
+        assert_eq!(
+            at("A <- [z-a]"),
+            (GrammarErrorKind::InvalidRange("z-a".to_string()), 0, 5)
+        );
+        assert_eq!(
+            at("A <- B 'x'\nB <- 'y'? A"),
+            (
+                GrammarErrorKind::LeftRecursion(vec![
+                    "A".to_string(),
+                    "B".to_string(),
+                    "A".to_string()
+                ]),
+                0,
+                0
+            )
+        );
+        // Right recursion is fine
+        assert!(Peg::new("A <- 'x' A / 'y'").is_ok());
+
+        // Syntax errors point at where the grammar stops making sense
+        let (kind, line, col) = at("A <- 'a' ('b'\nB <- 'b'");
+        assert!(matches!(kind, GrammarErrorKind::Syntax(_)));
+        assert_eq!((line, col), (1, 0));
+        let (kind, line, col) = at("A <- 'a' )");
+        assert_eq!(
+            kind,
+            GrammarErrorKind::Syntax(ParsingErrorKind::Expected("Item".to_string()))
+        );
+        assert_eq!((line, col), (0, 9));
+        // Including when the first item of a rule fails part of the way through
+        let (kind, line, col) = at("A <- ('b'");
+        assert_eq!(
+            kind,
+            GrammarErrorKind::Syntax(ParsingErrorKind::PatternNotFound(
+                " did not match pattern: )".to_string()
+            ))
+        );
+        assert_eq!((line, col), (0, 9));
+        for (grammar, col) in [("A <- \"\\q\"", 6), ("A <- 'a' \"\\q\"", 10)] {
+            let (kind, line, at_col) = at(grammar);
+            assert!(matches!(
+                kind,
+                GrammarErrorKind::Syntax(ParsingErrorKind::InvalidEscapeSequence(_))
+            ));
+            assert_eq!((line, at_col), (0, col));
+        }
+        let (kind, line, col) = at("A <- [a-z");
+        assert!(matches!(
+            kind,
+            GrammarErrorKind::Syntax(ParsingErrorKind::PatternNotFound(_))
+        ));
+        assert_eq!((line, col), (0, 9));
+        // A literal may not run on to the next line
+        let (_, line, col) = at("A <- 'a\nB <- 'b'");
+        assert_eq!((line, col), (0, 7));
+        let (kind, ..) = at("A 'a'");
+        assert_eq!(
+            kind,
+            GrammarErrorKind::Syntax(ParsingErrorKind::Expected("Arrow".to_string()))
+        );
+    }
+
+    #[test]
+    fn describe() {
+        let peg = Peg::new("List <- '[' Item* ']'\nItem <- [0-9] ','?").unwrap();
+        assert_eq!(
+            peg.describe().to_ebnf(),
+            "List = \"[\" , { Item } , \"]\" ;\nItem = ? [0-9] ? , [ \",\" ] ;\n"
+        );
+    }
+
+    /// Apply an edit, checking that reparsing gives the same result as a full parse
+    fn edit(peg: &Peg, parsed: IncrementalParse, edit: Edit) -> IncrementalParse {
+        let mut expected = parsed.source().to_string();
+        expected.replace_range(edit.start..edit.end, &edit.text);
+        let parsed = peg.reparse(parsed, &edit);
+        assert_eq!(parsed.source(), expected);
+        assert_eq!(parsed.result(), peg.parse(&expected).as_ref(), "{edit:?}");
+        parsed
+    }
+
+    #[test]
+    fn incremental() {
+        let peg = Peg::new(JSON).unwrap();
+        let source = "{\n  \"a\": [1, 2, 3],\n  \"b\": {\"c\": true},\n  \"d\": \"x\"\n}";
+        let parsed = peg.parse_incremental(source);
+        assert_eq!(parsed.result(), peg.parse(source).as_ref());
+        assert_eq!(parsed.reused(), 0);
+
+        // Replace a number, the members around it are not parsed again
+        let parsed = edit(&peg, parsed, Edit::new(13, 14, "20"));
+        assert!(parsed.reused() > 0);
+        let member = &parsed.result().unwrap().children[0].children[2];
+        assert_eq!(member.text(parsed.source()).trim_end(), "\"d\": \"x\"");
+        assert_eq!((member.span.start.line, member.span.start.col), (3, 2));
+
+        // Insert new lines, moving what follows down
+        let parsed = edit(&peg, parsed, Edit::new(20, 20, "\n\n"));
+        let member = &parsed.result().unwrap().children[0].children[2];
+        assert_eq!((member.span.start.line, member.span.start.col), (5, 2));
+
+        // Break the source, and then fix it again
+        let parsed = edit(&peg, parsed, Edit::new(4, 5, ""));
+        assert!(parsed.result().is_err());
+        let parsed = edit(&peg, parsed, Edit::new(4, 4, "\""));
+        assert!(parsed.result().is_ok());
+
+        // At the start and at the end
+        let parsed = edit(&peg, parsed, Edit::new(0, 0, "  "));
+        let end = parsed.source().len();
+        let parsed = edit(&peg, parsed, Edit::new(end, end, " ]"));
+        let end = parsed.source().len();
+        edit(&peg, parsed, Edit::new(end - 2, end, "\n"));
+    }
+
+    #[test]
+    fn incremental_shares_nodes() {
+        let peg = Peg::new(JSON).unwrap();
+        let parsed = peg.parse_incremental(r#"[{"a": 1}, {"b": 2}]"#);
+        let before = parsed.result().unwrap().children[0].clone();
+
+        // Neither object looked at the end of the array, so the new tree has the same nodes
+        let parsed = edit(&peg, parsed, Edit::new(19, 19, ", 3"));
+        let after = &parsed.result().unwrap().children[0];
+        assert_eq!(after.children.len(), 3);
+        assert!(Arc::ptr_eq(&before.children[0], &after.children[0]));
+        assert!(Arc::ptr_eq(
+            &before.children[1].children[0],
+            &after.children[1].children[0]
+        ));
+    }
+
+    #[test]
+    fn incremental_random_edits() {
+        let peg = Peg::new(JSON).unwrap();
+        let pieces = [
+            "1", "-", ".", "\"", "\\", "a", " ", "\n", ",", ":", "[", "]", "{", "}",
+        ];
+        let mut parsed = peg.parse_incremental(r#"{"a": [1, {"b": "c"}], "d": [2.5, -3]}"#);
+
+        // A small linear congruential generator, so that the edits are repeatable
+        let mut seed = 7u64;
+        let mut next = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % bound
+        };
+        for _ in 0..300 {
+            let len = parsed.source().len();
+            let start = next(len + 1);
+            let end = (start + next(3)).min(len);
+            let text = (0..next(3))
+                .map(|_| pieces[next(pieces.len())])
+                .collect::<String>();
+            let removed = parsed.source()[start..end].to_string();
+            parsed = edit(&peg, parsed, Edit::new(start, end, &text));
+
+            // Undo the edit, which makes the source valid again
+            parsed = edit(&peg, parsed, Edit::new(start, start + text.len(), &removed));
+            assert!(parsed.result().is_ok());
+            assert!(parsed.reused() > 0);
+        }
+    }
+}
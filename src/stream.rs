@@ -0,0 +1,421 @@
+use std::io::{self, Read};
+
+use crate::{
+    errors::{ParsingError, ParsingErrorKind},
+    inputs::{Input, Position},
+    traits::Parser,
+};
+
+/// An error from parsing a stream
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading from the source failed
+    Io(io::Error),
+    /// The source is not valid UTF-8, starting at this byte offset
+    InvalidUtf8(usize),
+    /// The parser failed. The line and column of the error are counted from the start of
+    /// the stream, and `offset` is the byte offset of the error in the stream.
+    Parsing { error: ParsingError, offset: usize },
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        StreamError::Io(error)
+    }
+}
+
+/// Parse a stream of values, one after another, from source that arrives in chunks.
+///
+/// Chunks of bytes are added with `feed`, and `finish` marks the end of the source. Until
+/// then, the buffered source is parsed as a partial input (see `Input::partial`), so a
+/// value that is cut off at the end of a chunk is parsed again once more source arrives.
+/// Only the source that has not been parsed yet is kept in memory.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::{number_p::integer, ParseMatch};
+/// use parlib::stream::StreamParser;
+/// use parlib::traits::Parser;
+///
+/// let mut stream = StreamParser::new(integer::<u32>().and_then(ParseMatch(";")));
+/// stream.feed(b"12;3").unwrap();
+/// assert_eq!(stream.next_output().unwrap().map(|(n, _)| n), Some(12));
+///
+/// // The 3 could be the start of a longer number
+/// assert!(stream.next_output().unwrap().is_none());
+///
+/// stream.feed(b"4;").unwrap();
+/// stream.finish();
+/// assert_eq!(stream.next_output().unwrap().map(|(n, _)| n), Some(34));
+/// assert!(stream.next_output().unwrap().is_none());
+/// assert!(stream.is_finished());
+/// ```
+#[derive(Debug)]
+pub struct StreamParser<P> {
+    parser: P,
+    /// The source that has been decoded, but not parsed yet
+    buffer: String,
+    /// The bytes at the end of the last chunk that are only part of a character
+    undecoded: Vec<u8>,
+    /// The position of the start of the buffer
+    position: Position,
+    finished: bool,
+    failed: bool,
+}
+
+impl<P> StreamParser<P>
+where
+    P: Parser,
+{
+    pub fn new(parser: P) -> Self {
+        StreamParser {
+            parser,
+            buffer: String::new(),
+            undecoded: vec![],
+            position: Position::default(),
+            finished: false,
+            failed: false,
+        }
+    }
+
+    /// Add the next chunk of the source
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), StreamError> {
+        self.undecoded.extend_from_slice(chunk);
+        let valid = match std::str::from_utf8(&self.undecoded) {
+            Ok(_) => self.undecoded.len(),
+            // The last character may be completed by the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => {
+                self.failed = true;
+                let offset = self.position.offset + self.buffer.len() + e.valid_up_to();
+                return Err(StreamError::InvalidUtf8(offset));
+            }
+        };
+
+        let decoded = self.undecoded.drain(..valid).collect::<Vec<_>>();
+        self.buffer
+            .push_str(std::str::from_utf8(&decoded).expect("the bytes were validated"));
+        Ok(())
+    }
+
+    /// Mark the end of the source, so that the buffered source is no longer partial
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// All of the source has been fed and parsed
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.buffer.is_empty()
+    }
+
+    /// The position in the stream of the source that has not been parsed yet
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// The source that has been fed, but not parsed yet
+    pub fn buffered(&self) -> &str {
+        &self.buffer
+    }
+
+    /// Parse the next value from the buffered source.
+    ///
+    /// `Ok(None)` is returned when more source is needed, or when all of it has been
+    /// parsed. Once an error is returned, no more values will be parsed.
+    pub fn next_output(&mut self) -> Result<Option<P::Output>, StreamError> {
+        if self.failed || self.buffer.is_empty() {
+            if self.finished && !self.undecoded.is_empty() && !self.failed {
+                self.failed = true;
+                let offset = self.position.offset + self.buffer.len();
+                return Err(StreamError::InvalidUtf8(offset));
+            }
+            return Ok(None);
+        }
+
+        let mut input = Input::new(
+            self.position.line,
+            self.position.col,
+            std::mem::take(&mut self.buffer),
+        )
+        .partial(!self.finished);
+        input.offset = self.position.offset;
+
+        match self.parser.parse(&input) {
+            Ok((_, rest)) if rest.offset == input.offset => {
+                self.buffer = input.source;
+                let kind = ParsingErrorKind::PatternNotFound(
+                    "the parser did not consume any input".to_string(),
+                );
+                Err(self.fail(ParsingError::new(kind, input.line, input.col)))
+            }
+            Ok((parsed, rest)) => {
+                self.position = rest.position();
+                self.buffer = rest.source;
+                Ok(Some(parsed))
+            }
+            Err(e) if e.is_incomplete() => {
+                self.buffer = input.source;
+                Ok(None)
+            }
+            Err(e) => {
+                self.buffer = input.source;
+                Err(self.fail(e))
+            }
+        }
+    }
+
+    /// No more values will be parsed, because the source has ended, or there was an error
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.finished || self.failed
+    }
+
+    /// Reading the next chunk of the source failed
+    pub(crate) fn read_failed(&mut self, error: io::Error) -> StreamError {
+        self.failed = true;
+        StreamError::Io(error)
+    }
+
+    fn fail(&mut self, error: ParsingError) -> StreamError {
+        self.failed = true;
+
+        // Find the byte offset of the error by walking the buffer from its start
+        let mut at = self.position;
+        for c in self.buffer.chars() {
+            if (at.line, at.col) >= (error.line(), error.col()) {
+                break;
+            }
+            at.offset += c.len_utf8();
+            if c == '\n' {
+                at.line += 1;
+                at.col = 0;
+            } else {
+                at.col += 1;
+            }
+        }
+
+        StreamError::Parsing {
+            error,
+            offset: at.offset,
+        }
+    }
+}
+
+/// Parse a stream of values, one after another, from anything that implements
+/// `io::Read`, reading more source whenever it is needed (see `StreamParser`).
+///
+/// This is an iterator over the parsed values. It stops at the end of the source, or
+/// after the first error.
+///
+/// # Example
+///
+/// ```rust
+/// use parlib::parsers::{number_p::integer, ParseMatch};
+/// use parlib::stream::ReadParser;
+/// use parlib::traits::Parser;
+///
+/// let source = "1\n22\n333\n".as_bytes();
+/// let line = integer::<u32>().and_then(ParseMatch("\n"));
+/// let numbers = ReadParser::new(source, line)
+///     .chunk_size(2)
+///     .map(|parsed| parsed.unwrap().0)
+///     .collect::<Vec<_>>();
+/// assert_eq!(numbers, vec![1, 22, 333]);
+/// ```
+#[derive(Debug)]
+pub struct ReadParser<R, P> {
+    reader: R,
+    stream: StreamParser<P>,
+    chunk: Vec<u8>,
+}
+
+impl<R, P> ReadParser<R, P>
+where
+    R: Read,
+    P: Parser,
+{
+    pub fn new(reader: R, parser: P) -> Self {
+        ReadParser {
+            reader,
+            stream: StreamParser::new(parser),
+            chunk: vec![0; 8 * 1024],
+        }
+    }
+
+    /// How many bytes to read at a time, 8 KiB by default
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        self.chunk = vec![0; size.max(1)];
+        self
+    }
+
+    /// The parser over the source that has been read so far
+    pub fn stream(&self) -> &StreamParser<P> {
+        &self.stream
+    }
+
+    fn read_chunk(&mut self) -> Result<(), StreamError> {
+        loop {
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => {
+                    self.stream.finish();
+                    return Ok(());
+                }
+                Ok(read) => return self.stream.feed(&self.chunk[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(self.stream.read_failed(e)),
+            }
+        }
+    }
+}
+
+impl<R, P> Iterator for ReadParser<R, P>
+where
+    R: Read,
+    P: Parser,
+{
+    type Item = Result<P::Output, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stream.next_output() {
+                Ok(Some(parsed)) => return Some(Ok(parsed)),
+                Err(e) => return Some(Err(e)),
+                Ok(None) if self.stream.is_stopped() => return None,
+                Ok(None) => {
+                    if let Err(e) = self.read_chunk() {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_stream {
+    use std::io::{self, Read};
+
+    use super::{ReadParser, StreamError, StreamParser};
+    use crate::{
+        parsers::{
+            and_p::KeepFirstOutputOnly, lexeme_p::identifier, number_p::integer,
+            string_p::string_parser, ParseMatch,
+        },
+        traits::Parser,
+    };
+
+    /// Generates `key<n> = <n>;\n` records, without ever holding all of them in memory
+    struct Records {
+        next: u64,
+        count: u64,
+        pending: Vec<u8>,
+    }
+
+    impl Read for Records {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            while self.pending.len() < buf.len() && self.next < self.count {
+                let record = format!("key{0} = {0};\n", self.next);
+                self.pending.extend_from_slice(record.as_bytes());
+                self.next += 1;
+            }
+            let len = buf.len().min(self.pending.len());
+            buf[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            Ok(len)
+        }
+    }
+
+    fn record() -> impl Parser<Output = (String, u64)> {
+        identifier()
+            .and_then(ParseMatch(" = "))
+            .combine(KeepFirstOutputOnly)
+            .and_then(integer::<u64>())
+            .and_then(ParseMatch(";\n"))
+            .combine(KeepFirstOutputOnly)
+    }
+
+    #[test]
+    fn large_source_in_chunks() {
+        let count = 200_000;
+        let source = Records {
+            next: 0,
+            count,
+            pending: vec![],
+        };
+
+        // About 4 MB of source, but only a few chunks are ever buffered
+        let mut parser = ReadParser::new(source, record()).chunk_size(100);
+        let mut parsed = 0;
+        let mut sum = 0;
+        while let Some(result) = parser.next() {
+            let (key, value) = result.unwrap();
+            assert_eq!(key, format!("key{value}"));
+            assert!(parser.stream().buffered().len() < 200);
+            parsed += 1;
+            sum += value;
+        }
+        assert_eq!(parsed, count);
+        assert_eq!(sum, count * (count - 1) / 2);
+        assert_eq!(parser.stream().position().line, count as usize);
+    }
+
+    #[test]
+    fn values_split_between_chunks() {
+        let value = string_parser()
+            .and_then(ParseMatch(","))
+            .combine(KeepFirstOutputOnly);
+        let mut stream = StreamParser::new(value);
+
+        // The chunks split a string, an escape, and a multi byte character
+        let source = "\"\u{e9}t\u{e9}\",\"a\\u00e9\",".as_bytes();
+        for chunk in source.chunks(3) {
+            stream.feed(chunk).unwrap();
+        }
+        stream.finish();
+        assert_eq!(
+            stream.next_output().unwrap(),
+            Some("\u{e9}t\u{e9}".to_string())
+        );
+        assert_eq!(stream.next_output().unwrap(), Some("a\u{e9}".to_string()));
+        assert!(stream.is_finished());
+
+        let mut stream = StreamParser::new(string_parser());
+        for chunk in source.chunks(1) {
+            stream.feed(chunk).unwrap();
+            if let Some(parsed) = stream.next_output().unwrap() {
+                assert_eq!(parsed, "\u{e9}t\u{e9}".to_string());
+                assert_eq!(stream.position().offset, 7);
+                return;
+            }
+        }
+        panic!("the string was never parsed");
+    }
+
+    #[test]
+    fn errors_at_stream_positions() {
+        let source = "a = 1;\nb = 2;\nc = x;\nd = 4;\n".as_bytes();
+        let mut parser = ReadParser::new(source, record()).chunk_size(4);
+        assert!(parser.next().unwrap().is_ok());
+        assert!(parser.next().unwrap().is_ok());
+        match parser.next().unwrap() {
+            Err(StreamError::Parsing { error, offset }) => {
+                assert_eq!((error.line(), error.col()), (2, 4));
+                assert_eq!(offset, 18);
+            }
+            other => panic!("expected a parsing error, found {other:?}"),
+        }
+        assert!(parser.next().is_none());
+
+        // A value that is cut off by the end of the source
+        let parser = ReadParser::new("a = 1;\nb = 2".as_bytes(), record());
+        let results = parser.collect::<Vec<_>>();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[1], Err(StreamError::Parsing { .. })));
+
+        let mut stream = StreamParser::new(record());
+        assert!(matches!(
+            stream.feed(b"a = \xff"),
+            Err(StreamError::InvalidUtf8(4))
+        ));
+    }
+}
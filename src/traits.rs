@@ -1,12 +1,20 @@
 use std::fmt::Debug;
 
 use crate::{
-    errors::ErrorParser,
-    inputs::Input,
+    describe::Description,
+    errors::{ErrorParser, ParsingError},
+    inputs::{Input, ParserInput},
     parsers::{
         and_p::{AndCombinator, AndThenParser, IdentityAndCombinator, KeepSecondOutputOnly},
+        cst_p::{CstNode, CstToken, Lossless},
+        iter_p::ParseIter,
+        lexeme_p::{Lexeme, TokenParser},
         map_p::{MapParser, TryMapParser},
+        named_p::Named,
         or_p::OrThenParser,
+        span_p::{Recognize, SpannedParser, WithSpan},
+        state_p::AndUpdateState,
+        trace_p::Traced,
     },
     type_alias::ParserRes,
 };
@@ -16,9 +24,15 @@ use crate::{
 /// A parser will parse some input string into A and also return the rest of the string
 /// ( or return an error ). This allows you to recursively keep parsing an input string until
 /// the entire input has been parsed.
-pub trait Parser
+///
+/// By default parsers work on an `Input` string, but they can work on any other
+/// `ParserInput`, such as a stream of tokens (see `parsers::token_p`). The combinators in this trait
+/// work for any input, while the ones that need to look at the source text (such as
+/// `recognize` or `lexeme`) are only available for `Input`.
+pub trait Parser<I = Input>
 where
     Self: Sized,
+    I: ParserInput,
 {
     type Output: Debug;
 
@@ -27,29 +41,45 @@ where
     /// over.
     ///
     /// If parsing did not suceed, then an error will be returned
-    fn parse(&self, input: &Input) -> ParserRes<Self::Output>;
+    fn parse(&self, input: &I) -> ParserRes<Self::Output, ParsingError, I>;
+
+    /// Describe the grammar that this parser accepts, see `describe::Grammar`. Parsers that
+    /// do not override this are described as opaque.
+    fn describe(&self) -> Description {
+        Description::opaque::<Self>()
+    }
 
     /// Parse the output (see parse function), and if sucessful, map the parsed output
-    fn parse_and_then_map<F, MappedOutput>(&self, input: &Input, f: F) -> ParserRes<MappedOutput>
+    fn parse_and_then_map<F, MappedOutput>(
+        &self,
+        input: &I,
+        f: F,
+    ) -> ParserRes<MappedOutput, ParsingError, I>
     where
         F: FnOnce(Self::Output) -> MappedOutput,
     {
         self.parse(input).map(|(a, rest)| (f(a), rest))
     }
 
+    /// Parse one record after another from the input, until the end of the input. See
+    /// `ParseIter`
+    fn iter(&self, input: &I) -> ParseIter<'_, Self, I> {
+        ParseIter::new(self, input.clone())
+    }
+
     /// Make a new parser that consists of this parser, followed by another parser.
     ///
     /// The output will be sucessful iff both parsers are sucessful
     fn and_then<P>(self, other: P) -> AndThenParser<Self, P, IdentityAndCombinator>
     where
-        P: Parser,
+        P: Parser<I>,
     {
         AndThenParser::from((self, other))
     }
 
     fn and_then_combine_with<P, C>(self, other: P, combinator: C) -> AndThenParser<Self, P, C>
     where
-        P: Parser,
+        P: Parser<I>,
         C: AndCombinator<Self::Output, P::Output>,
     {
         AndThenParser::from((self, other, combinator))
@@ -60,32 +90,26 @@ where
     /// This new parser will run both parsers in order, and return the first sucessful one
     fn otherwise<P>(self, other: P) -> OrThenParser<Self, P>
     where
-        P: Parser,
+        P: Parser<I>,
     {
         OrThenParser::from((self, other))
     }
 
-    fn with_mapping<T>(self, mapping: &'_ dyn Fn(Self::Output) -> T) -> MapParser<Self, T> {
-        MapParser {
-            parser: self,
-            mapping,
-        }
+    fn with_mapping<T>(self, mapping: &'_ dyn Fn(Self::Output) -> T) -> MapParser<'_, Self, T, I> {
+        MapParser::new(self, mapping)
     }
 
     fn with_try_mapping<T>(
         self,
         try_map: &'_ dyn Fn(Self::Output) -> Option<T>,
-    ) -> TryMapParser<Self, T> {
-        TryMapParser {
-            parser: self,
-            try_map,
-        }
+    ) -> TryMapParser<'_, Self, T, I> {
+        TryMapParser::new(self, try_map)
     }
 
     /// Preceed this parser with another parser
     fn preceed<P>(self, other: P) -> AndThenParser<P, Self, KeepSecondOutputOnly>
     where
-        P: Parser,
+        P: Parser<I>,
     {
         AndThenParser::from((other, self, KeepSecondOutputOnly))
     }
@@ -93,4 +117,81 @@ where
     fn with_error<'a>(self, message: &'a str) -> ErrorParser<'a, Self> {
         ErrorParser::new(self, message)
     }
+
+    /// Make this parser a named rule of the grammar, see `describe::Grammar`
+    fn named(self, name: &str) -> Named<Self> {
+        Named::new(self, name)
+    }
+
+    /// Record every run of this parser in the tracer, see `parsers::trace_p`
+    fn trace(self, name: &str) -> Traced<Self> {
+        Traced::new(self, name)
+    }
+
+    /// Output the text that this parser consumed, rather than its output
+    fn recognize(self) -> Recognize<Self>
+    where
+        Self: Parser,
+    {
+        Recognize::new(self)
+    }
+
+    /// Output the span of the source that this parser consumed, along with its output
+    fn with_span(self) -> WithSpan<Self>
+    where
+        Self: Parser,
+    {
+        WithSpan::new(self)
+    }
+
+    /// Wrap the output of this parser in a `Spanned`, for use in an AST
+    fn spanned(self) -> SpannedParser<Self>
+    where
+        Self: Parser,
+    {
+        SpannedParser::new(self)
+    }
+
+    /// After this parser succeeds, compute a new user state from the current state and
+    /// the output of this parser. See `Input::with_state`
+    fn and_update_state<S, F>(self, update: F) -> AndUpdateState<Self, S, F>
+    where
+        Self: Parser,
+        F: Fn(&S, &<Self as Parser>::Output) -> S,
+    {
+        AndUpdateState::new(self, update)
+    }
+
+    /// Skip any whitespace after this parser. To also skip comments, see `TokenParser::lexeme`
+    fn lexeme(self) -> Lexeme<Self>
+    where
+        Self: Parser,
+    {
+        TokenParser::new().lexeme(self)
+    }
+
+    /// Build a lossless tree of everything that this parser consumes, and output it along
+    /// with the output of this parser. See `cst_p::Lossless`
+    fn lossless(self, kind: &str) -> Lossless<Self>
+    where
+        Self: Parser,
+    {
+        Lossless::new(self, kind)
+    }
+
+    /// Make a node of the lossless tree out of whatever this parser consumes
+    fn node(self, kind: &str) -> CstNode<Self>
+    where
+        Self: Parser,
+    {
+        CstNode::new(self, kind)
+    }
+
+    /// Make a token of the lossless tree out of whatever this parser consumes
+    fn token(self, kind: &str) -> CstToken<Self>
+    where
+        Self: Parser,
+    {
+        CstToken::new(self, kind)
+    }
 }
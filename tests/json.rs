@@ -0,0 +1,31 @@
+#![cfg(feature = "json")]
+
+use std::{fs, path::Path};
+
+use parlib::formats::json::parse;
+
+/// Run every file of the vendored JSONTestSuite, see `tests/json_test_suite/README.md`
+#[test]
+fn json_test_suite() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/json_test_suite");
+    let mut failures = vec![];
+    let mut count = 0;
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let accept = match &name[..2] {
+            "y_" => true,
+            "n_" => false,
+            _ => continue,
+        };
+        let source = fs::read_to_string(&path).unwrap();
+        let parsed = parse(&source);
+        if parsed.is_ok() != accept {
+            failures.push(format!("{name}: {parsed:?}"));
+        }
+        count += 1;
+    }
+
+    assert!(count > 200);
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}